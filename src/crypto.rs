@@ -0,0 +1,205 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const NONCE_BYTES: usize = 12;
+const MASTER_KEY_BYTES: usize = 32;
+
+/// A secret encrypted under the master key, as stored on disk.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// AES-256-GCM under `HEVY_MASTER_KEY`. Every secret is bound to the id of the
+/// record holding it, so ciphertexts cannot be swapped between records.
+pub(crate) struct MasterKey {
+    cipher: Aes256Gcm,
+}
+
+impl MasterKey {
+    pub(crate) fn parse(master_key: &str) -> Result<Self, String> {
+        let key = from_hex(master_key.trim())
+            .filter(|key| key.len() == MASTER_KEY_BYTES)
+            .ok_or_else(|| {
+                "HEVY_MASTER_KEY must be 64 hex characters; generate one with \
+                `hevy-mcp-server master-key`"
+                    .to_string()
+            })?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|err| err.to_string())?;
+        Ok(Self { cipher })
+    }
+
+    pub(crate) fn seal(&self, secret: &str, record_id: &str) -> Result<Sealed, String> {
+        let mut nonce = [0u8; NONCE_BYTES];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: secret.as_bytes(),
+                    aad: record_id.as_bytes(),
+                },
+            )
+            .map_err(|_| "failed to encrypt a secret".to_string())?;
+        Ok(Sealed {
+            nonce: to_hex(&nonce),
+            ciphertext: to_hex(&ciphertext),
+        })
+    }
+
+    /// The secret, or `None` if it was sealed under another key or record.
+    pub(crate) fn open(&self, sealed: &Sealed, record_id: &str) -> Option<String> {
+        let nonce = from_hex(&sealed.nonce).filter(|nonce| nonce.len() == NONCE_BYTES)?;
+        let ciphertext = from_hex(&sealed.ciphertext)?;
+        let secret = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: record_id.as_bytes(),
+                },
+            )
+            .ok()?;
+        String::from_utf8(secret).ok()
+    }
+}
+
+/// A fresh random value for `HEVY_MASTER_KEY`.
+pub fn generate_master_key() -> String {
+    let mut key = [0u8; MASTER_KEY_BYTES];
+    OsRng.fill_bytes(&mut key);
+    to_hex(&key)
+}
+
+/// Replaces `path` with `contents`, readable only by the owner. Writes a
+/// sibling file and renames it so readers never see a partial file. The
+/// sibling's name is unique, so processes sharing `path` never write to the
+/// same one.
+pub(crate) fn write_private_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp_path = path.with_extension(format!("{}.tmp", Uuid::new_v4().simple()));
+    let result = open_private(&temp_path, true).and_then(|mut temp| {
+        temp.write_all(contents)?;
+        temp.sync_all()?;
+        fs::rename(&temp_path, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Takes an exclusive lock on a `.lock` file next to `path`, held until the
+/// returned file is dropped. Serializes read-modify-write cycles between
+/// processes that share `path`.
+pub(crate) fn lock_file(path: &Path) -> io::Result<File> {
+    let lock = open_private(&path.with_extension("lock"), false)?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+/// Identifies one version of a file replaced by `write_private_file`. Every
+/// write renames a new file into place, so the inode changes even when two
+/// writes land within the same modification time.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FileVersion {
+    modified: Option<SystemTime>,
+    len: u64,
+    #[cfg(unix)]
+    inode: u64,
+}
+
+impl FileVersion {
+    pub(crate) fn of(metadata: &fs::Metadata) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            #[cfg(unix)]
+            inode: std::os::unix::fs::MetadataExt::ino(metadata),
+        }
+    }
+}
+
+fn open_private(path: &Path, truncate: bool) -> io::Result<File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(truncate);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn master_key() -> MasterKey {
+        MasterKey::parse(&generate_master_key()).unwrap()
+    }
+
+    #[test]
+    fn sealed_secret_opens_with_the_same_record_id() {
+        let key = master_key();
+        let sealed = key.seal("hevy-api-key", "record-1").unwrap();
+        assert_eq!(
+            key.open(&sealed, "record-1").as_deref(),
+            Some("hevy-api-key")
+        );
+    }
+
+    #[test]
+    fn tampered_ciphertext_does_not_open() {
+        let key = master_key();
+        let mut sealed = key.seal("hevy-api-key", "record-1").unwrap();
+        let flipped = if sealed.ciphertext.starts_with('0') {
+            "1"
+        } else {
+            "0"
+        };
+        sealed.ciphertext.replace_range(..1, flipped);
+        assert_eq!(key.open(&sealed, "record-1"), None);
+    }
+
+    #[test]
+    fn secret_does_not_open_under_another_record_id() {
+        let key = master_key();
+        let sealed = key.seal("hevy-api-key", "record-1").unwrap();
+        assert_eq!(key.open(&sealed, "record-2"), None);
+    }
+
+    #[test]
+    fn secret_does_not_open_under_another_master_key() {
+        let sealed = master_key().seal("hevy-api-key", "record-1").unwrap();
+        assert_eq!(master_key().open(&sealed, "record-1"), None);
+    }
+
+    #[test]
+    fn master_key_must_be_64_hex_characters() {
+        assert!(MasterKey::parse("abcd").is_err());
+        assert!(MasterKey::parse(&"zz".repeat(32)).is_err());
+        assert!(MasterKey::parse(&format!(" {} ", generate_master_key())).is_ok());
+    }
+}
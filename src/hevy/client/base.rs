@@ -1,8 +1,13 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
 use reqwest::{Client, Method, StatusCode};
 use serde::de::DeserializeOwned;
 use serde_json::from_str;
 
 use crate::hevy::client::error::{HevyError, Result};
+use crate::hevy::types::ExerciseType;
 
 const HEVY_API_BASE_URL: &str = "https://api.hevyapp.com/v1";
 
@@ -13,20 +18,56 @@ pub(crate) fn paginated_endpoint(path: &str, page: Option<u32>, page_size: Optio
     }
 }
 
+/// A `reqwest::Client` tuned for the Hevy API. Clones share one connection
+/// pool, so build it once and hand it to every `HevyClient`.
+pub fn build_http_client() -> Client {
+    Client::builder()
+        .user_agent(concat!("hevy-mcp-server/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(10))
+        .timeout(Duration::from_secs(30))
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(64)
+        .tcp_keepalive(Duration::from_secs(60))
+        .http2_adaptive_window(true)
+        .http2_keep_alive_interval(Duration::from_secs(30))
+        .http2_keep_alive_while_idle(true)
+        .build()
+        .unwrap_or_default()
+}
+
+type UnauthorizedHook = Arc<dyn Fn() + Send + Sync>;
+
 #[derive(Clone)]
 pub struct HevyClient {
     client: Client,
     api_key: String,
+    on_unauthorized: Option<UnauthorizedHook>,
+    /// Types of the exercise templates fetched so far. A template's type
+    /// cannot change, so entries never go stale.
+    pub(crate) exercise_types: Arc<DashMap<String, ExerciseType>>,
 }
 
 impl HevyClient {
     pub fn new(api_key: impl Into<String>) -> Self {
+        Self::with_http_client(build_http_client(), api_key)
+    }
+
+    /// A handle that sends `api_key` over an existing connection pool.
+    pub fn with_http_client(client: Client, api_key: impl Into<String>) -> Self {
         Self {
-            client: Client::new(),
+            client,
             api_key: api_key.into(),
+            on_unauthorized: None,
+            exercise_types: Arc::new(DashMap::new()),
         }
     }
 
+    /// Runs `hook` whenever Hevy rejects the API key.
+    pub fn on_unauthorized(mut self, hook: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_unauthorized = Some(Arc::new(hook));
+        self
+    }
+
     pub(crate) fn request(&self, method: Method, endpoint: &str) -> reqwest::RequestBuilder {
         self.client
             .request(method, format!("{HEVY_API_BASE_URL}{endpoint}"))
@@ -40,7 +81,12 @@ impl HevyClient {
     ) -> Result<reqwest::Response> {
         match response.status() {
             StatusCode::OK | StatusCode::CREATED | StatusCode::NO_CONTENT => Ok(response),
-            StatusCode::UNAUTHORIZED => Err(HevyError::Unauthorized),
+            StatusCode::UNAUTHORIZED => {
+                if let Some(hook) = &self.on_unauthorized {
+                    hook();
+                }
+                Err(HevyError::Unauthorized)
+            }
             StatusCode::NOT_FOUND => {
                 let body = response.text().await.unwrap_or_default();
                 Err(HevyError::NotFound(body))
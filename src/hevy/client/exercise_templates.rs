@@ -1,5 +1,5 @@
 use reqwest::Method;
-use serde_json::json;
+use serde_json::{Value, json};
 
 use crate::hevy::client::error::HevyError;
 use crate::hevy::client::{HevyClient, Result, paginated_endpoint};
@@ -8,6 +8,10 @@ use crate::hevy::types::{
     ExerciseTemplateResponse, ExerciseTemplatesResponse, PaginatedResponse,
 };
 
+pub(crate) fn exercise_template_body(input: &CreateExerciseTemplateInput) -> Value {
+    json!({ "exercise": input })
+}
+
 impl HevyClient {
     pub async fn get_exercise_templates(
         &self,
@@ -25,7 +29,10 @@ impl HevyClient {
             .send()
             .await?;
         let result: ExerciseTemplateResponse = self.handle_response(response).await?;
-        Ok(result.into_exercise_template())
+        let template = result.into_exercise_template();
+        self.exercise_types
+            .insert(id.to_string(), template.exercise_type.clone());
+        Ok(template)
     }
 
     pub async fn create_exercise_template(
@@ -34,7 +41,7 @@ impl HevyClient {
     ) -> Result<CreateExerciseTemplateResponse> {
         let response = self
             .request(Method::POST, "/exercise_templates")
-            .json(&json!({ "exercise": input }))
+            .json(&exercise_template_body(&input))
             .send()
             .await?;
         let response = self.check_response(response).await?;
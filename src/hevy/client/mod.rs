@@ -6,6 +6,10 @@ mod routine_folders;
 mod routines;
 mod workouts;
 
-pub use base::HevyClient;
 pub(crate) use base::paginated_endpoint;
+pub use base::{HevyClient, build_http_client};
 pub use error::{HevyError, Result};
+pub(crate) use exercise_templates::exercise_template_body;
+pub(crate) use routine_folders::routine_folder_body;
+pub(crate) use routines::routine_body;
+pub(crate) use workouts::workout_body;
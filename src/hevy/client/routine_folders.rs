@@ -1,5 +1,5 @@
 use reqwest::Method;
-use serde_json::json;
+use serde_json::{Value, json};
 
 use crate::hevy::client::{HevyClient, Result, paginated_endpoint};
 use crate::hevy::types::{
@@ -7,6 +7,10 @@ use crate::hevy::types::{
     RoutineFoldersResponse,
 };
 
+pub(crate) fn routine_folder_body(folder: &RoutineFolderInput) -> Value {
+    json!({"routine_folder": folder})
+}
+
 impl HevyClient {
     pub async fn get_routine_folders(
         &self,
@@ -30,7 +34,7 @@ impl HevyClient {
     pub async fn create_routine_folder(&self, folder: RoutineFolderInput) -> Result<RoutineFolder> {
         let response = self
             .request(Method::POST, "/routine_folders")
-            .json(&json!({"routine_folder": folder}))
+            .json(&routine_folder_body(&folder))
             .send()
             .await?;
         let result: RoutineFolderResponse = self.handle_response(response).await?;
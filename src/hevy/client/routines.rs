@@ -1,11 +1,15 @@
 use reqwest::Method;
-use serde_json::json;
+use serde_json::{Value, json};
 
 use crate::hevy::client::{HevyClient, Result, paginated_endpoint};
 use crate::hevy::types::{
     PaginatedResponse, Routine, RoutineInput, RoutineResponse, RoutinesResponse,
 };
 
+pub(crate) fn routine_body(routine: &RoutineInput) -> Value {
+    json!({"routine": routine})
+}
+
 impl HevyClient {
     pub async fn get_routines(
         &self,
@@ -29,7 +33,7 @@ impl HevyClient {
     pub async fn create_routine(&self, routine: RoutineInput) -> Result<Routine> {
         let response = self
             .request(Method::POST, "/routines")
-            .json(&json!({"routine": routine}))
+            .json(&routine_body(&routine))
             .send()
             .await?;
         let result: RoutineResponse = self.handle_response(response).await?;
@@ -39,7 +43,7 @@ impl HevyClient {
     pub async fn update_routine(&self, id: &str, routine: RoutineInput) -> Result<Routine> {
         let response = self
             .request(Method::PUT, &format!("/routines/{id}"))
-            .json(&json!({"routine": routine}))
+            .json(&routine_body(&routine))
             .send()
             .await?;
         let result: RoutineResponse = self.handle_response(response).await?;
@@ -1,5 +1,5 @@
 use reqwest::Method;
-use serde_json::json;
+use serde_json::{Value, json};
 
 use crate::hevy::client::{HevyClient, Result, paginated_endpoint};
 use crate::hevy::types::{
@@ -7,6 +7,10 @@ use crate::hevy::types::{
     WorkoutResponse, WorkoutsResponse,
 };
 
+pub(crate) fn workout_body(workout: &WorkoutInput) -> Value {
+    json!({"workout": workout})
+}
+
 impl HevyClient {
     pub async fn get_workouts(
         &self,
@@ -60,7 +64,7 @@ impl HevyClient {
     pub async fn create_workout(&self, workout: WorkoutInput) -> Result<Workout> {
         let response = self
             .request(Method::POST, "/workouts")
-            .json(&json!({"workout": workout}))
+            .json(&workout_body(&workout))
             .send()
             .await?;
         let result: WorkoutResponse = self.handle_response(response).await?;
@@ -70,7 +74,7 @@ impl HevyClient {
     pub async fn update_workout(&self, id: &str, workout: WorkoutInput) -> Result<Workout> {
         let response = self
             .request(Method::PUT, &format!("/workouts/{id}"))
-            .json(&json!({"workout": workout}))
+            .json(&workout_body(&workout))
             .send()
             .await?;
         let result: WorkoutResponse = self.handle_response(response).await?;
@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::Deserializer;
 use serde::{Deserialize, Serialize};
 
@@ -67,7 +68,7 @@ pub struct PaginatedResponse<T> {
     pub data: T,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExerciseType {
     WeightReps,
@@ -77,10 +78,11 @@ pub enum ExerciseType {
     DistanceDuration,
     Weight,
     #[serde(other)]
+    #[schemars(skip)]
     Unknown,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum MuscleGroup {
     Abdominals,
@@ -104,10 +106,11 @@ pub enum MuscleGroup {
     UpperBack,
     Other,
     #[serde(other)]
+    #[schemars(skip)]
     Unknown,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Equipment {
     Barbell,
@@ -121,10 +124,11 @@ pub enum Equipment {
     SmithMachine,
     Other,
     #[serde(other)]
+    #[schemars(skip)]
     Unknown,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SetType {
     Normal,
@@ -132,5 +136,6 @@ pub enum SetType {
     Dropset,
     Failure,
     #[serde(other)]
+    #[schemars(skip)]
     Unknown,
 }
@@ -64,6 +64,21 @@ pub struct CreateExerciseTemplateInput {
     pub other_muscles: Option<Vec<MuscleGroup>>,
 }
 
+impl CreateExerciseTemplateInput {
+    /// The template Hevy would store for this input, for dry runs.
+    pub(crate) fn preview(&self, id: &str) -> ExerciseTemplate {
+        ExerciseTemplate {
+            id: id.to_string(),
+            title: self.title.clone(),
+            exercise_type: self.exercise_type.clone(),
+            primary_muscle_group: self.muscle_group.clone(),
+            secondary_muscle_groups: self.other_muscles.clone().unwrap_or_default(),
+            equipment: Some(self.equipment_category.clone()),
+            is_custom: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct CreateExerciseTemplateResponse {
     #[serde(deserialize_with = "deserialize_string_id")]
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use crate::hevy::types::common::{
     SetType, deserialize_option_string_id, deserialize_option_u32, deserialize_string_id,
 };
+use crate::hevy::types::workout::{SetInput, WorkoutExerciseInput};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Routine {
@@ -110,6 +111,33 @@ pub struct RoutineSet {
     pub custom_metric: Option<f64>,
 }
 
+impl From<&RoutineSet> for SetInput {
+    fn from(value: &RoutineSet) -> Self {
+        Self {
+            set_type: value.set_type.clone(),
+            weight_kg: value.weight_kg,
+            reps: value
+                .reps
+                .or_else(|| value.rep_range.as_ref().map(RepRange::midpoint)),
+            duration_seconds: value.duration_seconds,
+            distance_meters: value.distance_meters,
+            rpe: None,
+            custom_metric: value.custom_metric,
+        }
+    }
+}
+
+impl From<&RoutineExercise> for WorkoutExerciseInput {
+    fn from(value: &RoutineExercise) -> Self {
+        Self {
+            exercise_template_id: value.exercise_template_id.clone(),
+            superset_id: value.superset_id,
+            notes: value.notes.clone(),
+            sets: value.sets.iter().map(Into::into).collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutineInput {
     pub title: String,
@@ -134,7 +162,7 @@ pub struct RoutineExerciseInput {
     pub sets: Vec<RoutineSetInput>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RoutineSetInput {
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub set_type: Option<SetType>,
@@ -152,8 +180,92 @@ pub struct RoutineSetInput {
     pub custom_metric: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl RoutineInput {
+    /// The routine Hevy would store for this input, for dry runs.
+    pub(crate) fn preview(&self, id: &str) -> Routine {
+        Routine {
+            id: id.to_string(),
+            title: self.title.clone(),
+            notes: self.notes.clone(),
+            folder_id: self.folder_id.clone(),
+            exercises: self
+                .exercises
+                .iter()
+                .zip(0..)
+                .map(|(exercise, index)| RoutineExercise {
+                    index,
+                    exercise_template_id: exercise.exercise_template_id.clone(),
+                    superset_id: exercise.superset_id,
+                    rest_seconds: exercise.rest_seconds,
+                    notes: exercise.notes.clone(),
+                    sets: exercise
+                        .sets
+                        .iter()
+                        .zip(0..)
+                        .map(|(set, index)| RoutineSet {
+                            index,
+                            set_type: set.set_type.clone(),
+                            weight_kg: set.weight_kg,
+                            reps: set.reps,
+                            rep_range: set.rep_range.clone(),
+                            duration_seconds: set.duration_seconds,
+                            distance_meters: set.distance_meters,
+                            custom_metric: set.custom_metric,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+impl From<&Routine> for RoutineInput {
+    fn from(value: &Routine) -> Self {
+        Self {
+            title: value.title.clone(),
+            notes: value.notes.clone(),
+            folder_id: value.folder_id.clone(),
+            exercises: value.exercises.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&RoutineExercise> for RoutineExerciseInput {
+    fn from(value: &RoutineExercise) -> Self {
+        Self {
+            exercise_template_id: value.exercise_template_id.clone(),
+            superset_id: value.superset_id,
+            rest_seconds: value.rest_seconds,
+            notes: value.notes.clone(),
+            sets: value.sets.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&RoutineSet> for RoutineSetInput {
+    fn from(value: &RoutineSet) -> Self {
+        Self {
+            set_type: value.set_type.clone(),
+            weight_kg: value.weight_kg,
+            reps: value.reps,
+            rep_range: value.rep_range.clone(),
+            duration_seconds: value.duration_seconds,
+            distance_meters: value.distance_meters,
+            custom_metric: value.custom_metric,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RepRange {
     pub start: u32,
     pub end: u32,
 }
+
+impl RepRange {
+    pub fn midpoint(&self) -> u32 {
+        self.start + self.end.saturating_sub(self.start) / 2
+    }
+}
@@ -58,3 +58,16 @@ pub struct RoutineFolderInput {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub index: Option<u32>,
 }
+
+impl RoutineFolderInput {
+    /// The folder Hevy would store for this input, for dry runs.
+    pub(crate) fn preview(&self, id: &str) -> RoutineFolder {
+        RoutineFolder {
+            id: id.to_string(),
+            title: self.title.clone(),
+            index: self.index.unwrap_or_default(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
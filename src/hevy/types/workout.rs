@@ -119,6 +119,8 @@ pub struct WorkoutInput {
     pub title: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routine_id: Option<String>,
     pub is_private: bool,
     pub start_time: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -138,7 +140,7 @@ pub struct WorkoutExerciseInput {
     pub sets: Vec<SetInput>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SetInput {
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub set_type: Option<SetType>,
@@ -156,6 +158,88 @@ pub struct SetInput {
     pub custom_metric: Option<f64>,
 }
 
+impl WorkoutInput {
+    /// The workout Hevy would store for this input, for dry runs.
+    pub(crate) fn preview(&self, id: &str) -> Workout {
+        Workout {
+            id: id.to_string(),
+            title: self.title.clone(),
+            routine_id: self.routine_id.clone(),
+            description: self.description.clone(),
+            is_private: self.is_private,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            exercises: self
+                .exercises
+                .iter()
+                .zip(0..)
+                .map(|(exercise, index)| WorkoutExercise {
+                    index,
+                    exercise_template_id: exercise.exercise_template_id.clone(),
+                    superset_id: exercise.superset_id,
+                    notes: exercise.notes.clone(),
+                    sets: exercise
+                        .sets
+                        .iter()
+                        .zip(0..)
+                        .map(|(set, index)| ExerciseSet {
+                            index,
+                            set_type: set.set_type.clone().unwrap_or(SetType::Normal),
+                            weight_kg: set.weight_kg,
+                            reps: set.reps,
+                            duration_seconds: set.duration_seconds,
+                            distance_meters: set.distance_meters,
+                            rpe: set.rpe,
+                            custom_metric: set.custom_metric,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+impl From<&Workout> for WorkoutInput {
+    fn from(value: &Workout) -> Self {
+        Self {
+            title: value.title.clone(),
+            description: value.description.clone(),
+            routine_id: value.routine_id.clone(),
+            is_private: value.is_private,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            exercises: value.exercises.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&WorkoutExercise> for WorkoutExerciseInput {
+    fn from(value: &WorkoutExercise) -> Self {
+        Self {
+            exercise_template_id: value.exercise_template_id.clone(),
+            superset_id: value.superset_id,
+            notes: value.notes.clone(),
+            sets: value.sets.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&ExerciseSet> for SetInput {
+    fn from(value: &ExerciseSet) -> Self {
+        Self {
+            set_type: Some(value.set_type.clone()),
+            weight_kg: value.weight_kg,
+            reps: value.reps,
+            duration_seconds: value.duration_seconds,
+            distance_meters: value.distance_meters,
+            rpe: value.rpe,
+            custom_metric: value.custom_metric,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkoutCountResponse {
     pub workout_count: u32,
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::handler::{bearer_token, header_value};
+use crate::tokens::{TokenError, TokenStore, fingerprint};
+
+#[derive(Clone)]
+struct AdminState {
+    tokens: Arc<TokenStore>,
+    admin_token: Arc<str>,
+}
+
+#[derive(Deserialize)]
+struct IssueTokenRequest {
+    api_key: String,
+    label: Option<String>,
+}
+
+/// Token management endpoints under `{base}/admin/tokens`, authorized by the
+/// `HEVY_ADMIN_TOKEN` bearer token.
+pub(crate) fn routes(base: &str, tokens: Arc<TokenStore>, admin_token: String) -> Router {
+    let path = format!("{}/admin/tokens", base.trim_end_matches('/'));
+    Router::new()
+        .route(&path, get(list_tokens).post(issue_token))
+        .route(&format!("{path}/:id"), delete(revoke_token))
+        .with_state(AdminState {
+            tokens,
+            admin_token: admin_token.into(),
+        })
+}
+
+async fn issue_token(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Json(body): Json<IssueTokenRequest>,
+) -> Response {
+    if let Some(response) = reject_unauthorized(&state, &headers) {
+        return response;
+    }
+    if body.api_key.trim().is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, "api_key must not be empty");
+    }
+    match state.tokens.issue(body.api_key.trim(), body.label) {
+        Ok((token, info)) => {
+            info!("Issued access token {}", info.id);
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "token": token,
+                    "id": info.id,
+                    "label": info.label,
+                    "created_at": info.created_at,
+                })),
+            )
+                .into_response()
+        }
+        Err(err) => store_error(err),
+    }
+}
+
+async fn list_tokens(State(state): State<AdminState>, headers: HeaderMap) -> Response {
+    if let Some(response) = reject_unauthorized(&state, &headers) {
+        return response;
+    }
+    match state.tokens.list() {
+        Ok(tokens) => Json(json!({ "tokens": tokens })).into_response(),
+        Err(err) => store_error(err),
+    }
+}
+
+async fn revoke_token(
+    State(state): State<AdminState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Some(response) = reject_unauthorized(&state, &headers) {
+        return response;
+    }
+    match state.tokens.revoke(&id) {
+        Ok(info) => {
+            info!("Revoked access token {}", info.id);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(err) => store_error(err),
+    }
+}
+
+fn reject_unauthorized(state: &AdminState, headers: &HeaderMap) -> Option<Response> {
+    let presented = header_value(headers, header::AUTHORIZATION.as_str()).and_then(bearer_token);
+    // Comparing digests keeps the comparison time independent of the admin token.
+    match presented {
+        Some(token) if fingerprint(token) == fingerprint(&state.admin_token) => None,
+        _ => Some(
+            (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                Json(json!({ "error": "Admin token required" })),
+            )
+                .into_response(),
+        ),
+    }
+}
+
+fn store_error(err: TokenError) -> Response {
+    match err {
+        TokenError::NotFound(_) => error_response(StatusCode::NOT_FOUND, &err.to_string()),
+        err => {
+            warn!("Token store error: {}", err);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Token store error")
+        }
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
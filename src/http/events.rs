@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, ready};
+
+use serde::Serialize;
+use serde_json::to_string;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt, iter};
+
+const EVENT_HISTORY: usize = 256;
+
+/// The `GET` stream that carries messages not tied to any request.
+pub(crate) const STANDALONE_STREAM: u64 = 0;
+
+#[derive(Clone, Debug)]
+pub(crate) struct SessionEvent {
+    pub(crate) id: u64,
+    pub(crate) stream: u64,
+    pub(crate) data: String,
+    /// Set on the last event of a `POST` response stream.
+    pub(crate) closes_stream: bool,
+}
+
+/// Server-to-client messages for one session. Events are numbered across all of
+/// the session's streams so a `Last-Event-ID` identifies both the stream and the
+/// position to resume from.
+#[derive(Debug)]
+pub(crate) struct EventLog {
+    history: Mutex<VecDeque<SessionEvent>>,
+    next_id: AtomicU64,
+    next_stream: AtomicU64,
+    sender: broadcast::Sender<SessionEvent>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_HISTORY);
+        Self {
+            history: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1),
+            next_stream: AtomicU64::new(STANDALONE_STREAM + 1),
+            sender,
+        }
+    }
+}
+
+impl EventLog {
+    pub(crate) fn open_stream(&self) -> u64 {
+        self.next_stream.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub(crate) fn publish<T: Serialize>(&self, stream: u64, message: &T, closes_stream: bool) {
+        let data = to_string(message).unwrap_or_else(|_| "{}".to_string());
+        let mut history = self.history.lock().unwrap();
+        let event = SessionEvent {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            stream,
+            data,
+            closes_stream,
+        };
+        if history.len() == EVENT_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(event.clone());
+        // No receivers just means no stream is open right now.
+        let _ = self.sender.send(event);
+    }
+
+    /// The stream a previously delivered event belonged to, if it is still retained.
+    pub(crate) fn stream_of(&self, event_id: u64) -> Option<u64> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|event| event.id == event_id)
+            .map(|event| event.stream)
+    }
+
+    /// Events on `stream`, starting after `last_event_id` when resuming or with
+    /// the next published event otherwise. Ends after an event that closes the stream.
+    pub(crate) fn subscribe(
+        &self,
+        stream: u64,
+        last_event_id: Option<u64>,
+    ) -> impl Stream<Item = SessionEvent> + Send + 'static {
+        let history = self.history.lock().unwrap();
+        // Subscribing while the history is locked means nothing falls between
+        // the replayed events and the live ones.
+        let receiver = self.sender.subscribe();
+        let replay: Vec<SessionEvent> = match last_event_id {
+            Some(last) => history
+                .iter()
+                .filter(|event| event.stream == stream && event.id > last)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        let closed = history
+            .iter()
+            .any(|event| event.stream == stream && event.closes_stream);
+        drop(history);
+
+        // A closed stream that has nothing left to replay ends straight away.
+        let finished = closed && !replay.iter().any(|event| event.closes_stream);
+        let live = BroadcastStream::new(receiver)
+            // A lagged receiver only loses events; keep the stream open.
+            .filter_map(Result::ok)
+            .filter(move |event| event.stream == stream);
+        UntilClosed {
+            inner: Box::pin(iter(replay).chain(live)),
+            finished,
+        }
+    }
+}
+
+struct UntilClosed {
+    inner: Pin<Box<dyn Stream<Item = SessionEvent> + Send>>,
+    finished: bool,
+}
+
+impl Stream for UntilClosed {
+    type Item = SessionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<SessionEvent>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+        let next = ready!(self.inner.as_mut().poll_next(cx));
+        self.finished = next.as_ref().is_none_or(|event| event.closes_stream);
+        Poll::Ready(next)
+    }
+}
@@ -1,63 +1,102 @@
 use crate::HevyRouter;
-use crate::http::session::SessionManager;
-use crate::router::RequestRouter;
+use crate::http::events::{EventLog, STANDALONE_STREAM, SessionEvent};
+use crate::http::oauth::{OAuthApiKey, OAuthServer};
+use crate::http::session::{ApiKeySource, NewSessionError, SessionManager, SessionState};
+use crate::http::store::SessionCredential;
+use crate::router::{ProtocolVersion, RequestRouter, ToolPolicy, progress_token};
+use crate::tokens::{TokenStore, fingerprint, is_token};
 use axum::body::{Body, to_bytes};
-use axum::extract::{Query, State};
+use axum::extract::{Extension, Query, State};
 use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
-use mcp_server::router::RouterService;
 use mcp_spec::protocol::{
     ErrorData, INTERNAL_ERROR, INVALID_REQUEST, JsonRpcError, JsonRpcMessage, JsonRpcNotification,
     JsonRpcRequest, JsonRpcResponse, PARSE_ERROR,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::{from_slice, to_string};
+use serde_json::{Value, from_slice, from_value, to_string};
+use std::convert::Infallible;
 use std::sync::Arc;
-use tower_service::Service;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tokio_stream::{Stream, StreamExt};
+use tracing::warn;
 
 const MCP_SESSION_HEADER: &str = "Mcp-Session-Id";
+const HEVY_API_KEY_HEADER: &str = "X-Hevy-Api-Key";
+const LAST_EVENT_ID_HEADER: &str = "Last-Event-ID";
+const PROTOCOL_VERSION_HEADER: &str = "MCP-Protocol-Version";
+const EVENT_STREAM_MIME_TYPE: &str = "text/event-stream";
+/// JSON-RPC error code for requests refused by a session or tool call limit.
+const LIMIT_EXCEEDED: i32 = -32029;
+/// Largest request body read, in bytes.
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+/// Most messages accepted in one JSON-RPC batch.
+const MAX_BATCH_SIZE: usize = 32;
+/// Most requests from one batch dispatched at the same time.
+const MAX_BATCH_CONCURRENCY: usize = 4;
 
 #[derive(Clone)]
 pub(crate) struct AppState {
     pub(crate) router: Arc<HevyRouter>,
     pub(crate) session_manager: Arc<SessionManager>,
+    pub(crate) allow_query_api_key: bool,
+    pub(crate) tokens: Option<Arc<TokenStore>>,
+    pub(crate) oauth: Option<Arc<OAuthServer>>,
 }
 
 #[derive(Deserialize)]
 pub(crate) struct McpQuery {
     apikey: Option<String>,
+    #[serde(default)]
+    read_only: bool,
+    allow_tools: Option<String>,
+    deny_tools: Option<String>,
 }
 
 pub(crate) async fn mcp_handler(
     State(state): State<AppState>,
     Query(query): Query<McpQuery>,
+    oauth_api_key: Option<Extension<OAuthApiKey>>,
     headers: HeaderMap,
     body: Body,
 ) -> Response {
-    let body_bytes = match to_bytes(body, usize::MAX).await {
+    let oauth_api_key = oauth_api_key.map(|Extension(OAuthApiKey(api_key))| api_key);
+    let body_bytes = match to_bytes(body, MAX_BODY_BYTES).await {
         Ok(bytes) => bytes,
         Err(err) => {
-            return error_response(
-                INTERNAL_ERROR,
-                format!("Failed to read request body: {}", err),
-            );
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                json_body(error_json(
+                    None,
+                    INVALID_REQUEST,
+                    format!(
+                        "Failed to read request body of at most {} bytes: {}",
+                        MAX_BODY_BYTES, err
+                    ),
+                )),
+            )
+                .into_response();
         }
     };
 
+    if body_bytes.trim_ascii_start().starts_with(b"[") {
+        return handle_batch(state, &headers, &body_bytes).await;
+    }
+
     let message: JsonRpcMessage = match from_slice(&body_bytes) {
         Ok(msg) => msg,
         Err(err) => return error_response(PARSE_ERROR, format!("Invalid JSON: {}", err)),
     };
 
-    let session_id = headers
-        .get(MCP_SESSION_HEADER)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
-
     match message {
-        JsonRpcMessage::Request(request) => handle_request(state, query, session_id, request).await,
+        JsonRpcMessage::Request(request) => {
+            handle_request(state, query, oauth_api_key, &headers, request).await
+        }
         JsonRpcMessage::Notification(notification) => {
-            handle_notification(state, session_id, notification).await
+            handle_notification(state, session_id_header(&headers), notification).await
         }
         _ => error_response(
             INVALID_REQUEST,
@@ -66,17 +105,217 @@ pub(crate) async fn mcp_handler(
     }
 }
 
+enum BatchReply {
+    Ready(JsonRpcMessage),
+    Pending(Option<u64>, JoinHandle<JsonRpcResponse>),
+}
+
+/// Dispatches the requests in a JSON-RPC batch, at most
+/// `MAX_BATCH_CONCURRENCY` at a time, and answers with their responses in the
+/// order the requests were sent.
+async fn handle_batch(state: AppState, headers: &HeaderMap, body: &[u8]) -> Response {
+    let items: Vec<Value> = match from_slice(body) {
+        Ok(items) => items,
+        Err(err) => return error_response(PARSE_ERROR, format!("Invalid JSON: {}", err)),
+    };
+    if items.is_empty() {
+        return error_response(INVALID_REQUEST, "Batch must not be empty".to_string());
+    }
+    if items.len() > MAX_BATCH_SIZE {
+        return error_response(
+            INVALID_REQUEST,
+            format!("Batch must not have more than {} messages", MAX_BATCH_SIZE),
+        );
+    }
+    let messages: Vec<Result<JsonRpcMessage, String>> = items
+        .into_iter()
+        .map(|item| from_value(item).map_err(|err| err.to_string()))
+        .collect();
+
+    let has_requests = messages
+        .iter()
+        .any(|message| matches!(message, Ok(JsonRpcMessage::Request(_))));
+    let session_id = session_id_header(headers);
+    let request_router = if has_requests {
+        let Some(session_id) = &session_id else {
+            return error_response(
+                INVALID_REQUEST,
+                "Missing Mcp-Session-Id header. Call initialize first.".to_string(),
+            );
+        };
+        let Some(session) = state.session_manager.get_session(session_id) else {
+            return (
+                StatusCode::NOT_FOUND,
+                json_body(error_json(
+                    None,
+                    INVALID_REQUEST,
+                    "Session not found or expired".to_string(),
+                )),
+            )
+                .into_response();
+        };
+        if let Err(message) =
+            check_protocol_version(protocol_version_header(headers).as_deref(), &session)
+        {
+            return (
+                StatusCode::BAD_REQUEST,
+                json_body(error_json(None, INVALID_REQUEST, message)),
+            )
+                .into_response();
+        }
+        state.session_manager.update_activity(session_id);
+        Some((session_router(&state, &session), session))
+    } else {
+        None
+    };
+
+    let permits = Arc::new(Semaphore::new(MAX_BATCH_CONCURRENCY));
+    let mut replies = Vec::new();
+    let mut limited = 0;
+    for message in messages {
+        match message {
+            Ok(JsonRpcMessage::Request(request)) if request.method == "initialize" => {
+                replies.push(BatchReply::Ready(error_json(
+                    request.id,
+                    INVALID_REQUEST,
+                    "initialize must not be part of a batch".to_string(),
+                )));
+            }
+            Ok(JsonRpcMessage::Request(request)) => {
+                let (request_router, session) =
+                    request_router.as_ref().expect("batch has a session");
+                if request.method == "tools/call"
+                    && let Err(retry_after) = state.session_manager.acquire_tool_call(session)
+                {
+                    limited += 1;
+                    replies.push(BatchReply::Ready(error_json(
+                        request.id,
+                        LIMIT_EXCEEDED,
+                        tool_call_limit_message(retry_after),
+                    )));
+                    continue;
+                }
+                let request_router = request_router.clone();
+                let id = request.id;
+                let permits = permits.clone();
+                let reply = tokio::spawn(async move {
+                    let _permit = permits.acquire_owned().await;
+                    request_router.respond(request).await
+                });
+                replies.push(BatchReply::Pending(id, reply));
+            }
+            Ok(JsonRpcMessage::Notification(notification)) => {
+                handle_notification(state.clone(), session_id.clone(), notification).await;
+            }
+            Ok(_) => replies.push(BatchReply::Ready(error_json(
+                None,
+                INVALID_REQUEST,
+                "Expected request or notification".to_string(),
+            ))),
+            Err(err) => replies.push(BatchReply::Ready(error_json(
+                None,
+                INVALID_REQUEST,
+                format!("Invalid message: {}", err),
+            ))),
+        }
+    }
+
+    // A batch of notifications gets no response body, like a single notification.
+    if replies.is_empty() {
+        return (StatusCode::ACCEPTED, "").into_response();
+    }
+
+    // Only a batch that was refused outright is reported as rate limited.
+    let status = if limited == replies.len() {
+        StatusCode::TOO_MANY_REQUESTS
+    } else {
+        StatusCode::OK
+    };
+    let mut responses = Vec::with_capacity(replies.len());
+    for reply in replies {
+        responses.push(match reply {
+            BatchReply::Ready(message) => message,
+            BatchReply::Pending(id, reply) => match reply.await {
+                Ok(response) => JsonRpcMessage::Response(response),
+                Err(err) => error_json(id, INTERNAL_ERROR, format!("Request failed: {}", err)),
+            },
+        });
+    }
+
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/json")],
+        json_body(responses),
+    )
+        .into_response()
+}
+
+fn session_router(state: &AppState, session: &SessionState) -> RequestRouter {
+    let request_router = RequestRouter::new(
+        state.router.clone(),
+        session.client_lease.clone(),
+        session.confirmations.clone(),
+        session.policy.clone(),
+        session.subscriptions.clone(),
+    )
+    .with_protocol_version(session.protocol_version);
+    // A signed session's requests may each reach a different replica, and
+    // notifications only reach streams open on the replica that polls.
+    match session.signed {
+        Some(_) => request_router.without_subscriptions(),
+        None => request_router,
+    }
+}
+
+pub(crate) fn session_id_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(MCP_SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains(EVENT_STREAM_MIME_TYPE))
+}
+
+fn protocol_version_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(PROTOCOL_VERSION_HEADER)
+        .map(|v| v.to_str().unwrap_or_default().trim().to_string())
+}
+
+/// Requests after `initialize` may repeat the negotiated version in the
+/// `MCP-Protocol-Version` header. Leaving it out is allowed for older clients.
+fn check_protocol_version(header: Option<&str>, session: &SessionState) -> Result<(), String> {
+    let Some(header) = header else {
+        return Ok(());
+    };
+    match ProtocolVersion::parse(header) {
+        Some(version) if version == session.protocol_version => Ok(()),
+        Some(_) => Err(format!(
+            "MCP-Protocol-Version {} does not match the version negotiated for this session ({})",
+            header,
+            session.protocol_version.as_str()
+        )),
+        None => Err(format!("Unsupported MCP-Protocol-Version: {}", header)),
+    }
+}
+
 async fn handle_request(
     state: AppState,
     query: McpQuery,
-    session_id: Option<String>,
+    oauth_api_key: Option<String>,
+    headers: &HeaderMap,
     request: JsonRpcRequest,
 ) -> Response {
     if request.method == "initialize" {
-        return handle_initialize(state, query, request).await;
+        return handle_initialize(state, query, oauth_api_key, headers, request).await;
     }
 
-    let session_id = match session_id {
+    let session_id = match session_id_header(headers) {
         Some(id) => id,
         None => {
             return error_response(
@@ -101,69 +340,114 @@ async fn handle_request(
         }
     };
 
+    if let Err(message) =
+        check_protocol_version(protocol_version_header(headers).as_deref(), &session)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            json_body(error_json(request.id, INVALID_REQUEST, message)),
+        )
+            .into_response();
+    }
+
     state.session_manager.update_activity(&session_id);
 
-    let request_router = RequestRouter::new(state.router.clone(), session.api_key.clone());
-    let mut service = RouterService(request_router);
+    if request.method == "tools/call"
+        && let Err(retry_after) = state.session_manager.acquire_tool_call(&session)
+    {
+        return limit_response(
+            request.id,
+            tool_call_limit_message(retry_after),
+            Some(retry_after),
+        );
+    }
 
-    let id = request.id;
-    let response = match service.call(request).await {
-        Ok(response) => response,
-        Err(err) => {
-            let error_message = format!("{:?}", err);
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id,
-                result: None,
-                error: Some(ErrorData {
-                    code: INTERNAL_ERROR,
-                    message: error_message,
-                    data: None,
-                }),
-            }
-        }
-    };
+    let request_router = session_router(&state, &session);
 
-    json_response(response)
+    if accepts_event_stream(headers) && progress_token(&request).is_some() {
+        return stream_request(state, session_id, session.events, request_router, request);
+    }
+
+    json_response(request_router.respond(request).await)
 }
 
-async fn handle_initialize(state: AppState, query: McpQuery, request: JsonRpcRequest) -> Response {
-    let api_key = match query
-        .apikey
-        .or_else(|| state.router.default_api_key.clone())
-    {
-        Some(key) => key,
-        None => {
-            return error_response(
-                INVALID_REQUEST,
-                "API key required. Provide via ?apikey=xxx query parameter or HEVY_API_KEY environment variable".to_string(),
-            )
-        }
-    };
+/// Answers a request with its own SSE stream, so notifications about the
+/// request reach the client before the response that closes the stream.
+fn stream_request(
+    state: AppState,
+    session_id: String,
+    events: Arc<EventLog>,
+    request_router: RequestRouter,
+    request: JsonRpcRequest,
+) -> Response {
+    let stream = events.open_stream();
+    let request_events = events.clone();
+    let request_router = request_router.with_notifications(Arc::new(move |notification| {
+        request_events.publish(stream, &notification, false)
+    }));
 
-    let session = state.session_manager.new_session(api_key);
+    let body = events.subscribe(stream, None);
+    // The request runs to completion even if the client disconnects, so the
+    // response can still be picked up by resuming the stream.
+    tokio::spawn(async move {
+        let response = request_router.respond(request).await;
+        events.publish(stream, &response, true);
+    });
+    event_stream_response(state, session_id, body)
+}
+
+async fn handle_initialize(
+    state: AppState,
+    query: McpQuery,
+    oauth_api_key: Option<String>,
+    headers: &HeaderMap,
+    request: JsonRpcRequest,
+) -> Response {
+    let resolved = match oauth_api_key {
+        Some(api_key) => Ok((api_key, None, ApiKeySource::OAuth)),
+        None => resolve_api_key(&state, &query, headers),
+    };
+    let (api_key, token_id, api_key_source) = match resolved {
+        Ok(key) => key,
+        Err(message) => return error_response(INVALID_REQUEST, message),
+    };
 
-    let request_router = RequestRouter::new(state.router.clone(), session.api_key.clone());
-    let mut service = RouterService(request_router);
+    let policy = match ToolPolicy::new(
+        state.router.tools(),
+        query.read_only,
+        query.allow_tools.as_deref(),
+        query.deny_tools.as_deref(),
+    ) {
+        Ok(policy) => policy,
+        Err(message) => return error_response(INVALID_REQUEST, message),
+    };
 
-    let id = request.id;
-    let response = match service.call(request).await {
-        Ok(response) => response,
+    let client_lease = state.router.lease_client(&api_key);
+    let credential = match token_id {
+        Some(id) => SessionCredential::Token(id),
+        None => SessionCredential::ApiKey(api_key),
+    };
+    let session = match state.session_manager.new_session(
+        credential,
+        api_key_source,
+        policy,
+        ProtocolVersion::negotiate(&request),
+        client_lease,
+    ) {
+        Ok(session) => session,
+        Err(NewSessionError::Store(err)) => {
+            warn!("Failed to create a session: {}", err);
+            return error_response(INTERNAL_ERROR, "Failed to create a session".to_string());
+        }
         Err(err) => {
-            let error_message = format!("{:?}", err);
-            JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                id,
-                result: None,
-                error: Some(ErrorData {
-                    code: INTERNAL_ERROR,
-                    message: error_message,
-                    data: None,
-                }),
-            }
+            warn!("Refused a new session: {}", err);
+            return limit_response(request.id, err.to_string(), None);
         }
     };
 
+    let request_router = session_router(&state, &session);
+    let response = request_router.respond(request).await;
+
     let mut headers = HeaderMap::new();
     if let Ok(header_value) = session.session_id.parse() {
         headers.insert(MCP_SESSION_HEADER, header_value);
@@ -173,6 +457,87 @@ async fn handle_initialize(state: AppState, query: McpQuery, request: JsonRpcReq
     (StatusCode::OK, headers, json_body(response)).into_response()
 }
 
+/// Takes the Hevy API key from the `X-Hevy-Api-Key` header, an
+/// `Authorization: Bearer` header, the `apikey` query parameter (unless
+/// disabled) or the server's default key, in that order. When a token store is
+/// configured, clients send a server-issued token there instead of a key, and
+/// the token's id comes back with the key.
+fn resolve_api_key(
+    state: &AppState,
+    query: &McpQuery,
+    headers: &HeaderMap,
+) -> Result<(String, Option<String>, ApiKeySource), String> {
+    let Some((credential, source)) = client_credential(state, query, headers)? else {
+        return state
+            .router
+            .default_api_key
+            .clone()
+            .map(|key| (key, None, ApiKeySource::Default))
+            .ok_or_else(|| {
+                "API key required. Provide it in an Authorization: Bearer or X-Hevy-Api-Key \
+                header, the ?apikey=xxx query parameter, or the HEVY_API_KEY environment variable"
+                    .to_string()
+            });
+    };
+
+    match &state.tokens {
+        Some(tokens) if is_token(&credential) => match tokens.resolve(&credential) {
+            Ok(Some(api_key)) => Ok((api_key, Some(fingerprint(&credential)), ApiKeySource::Token)),
+            Ok(None) => Err("Unknown or revoked access token".to_string()),
+            Err(err) => {
+                warn!("Access token lookup failed: {}", err);
+                Err("Access token lookup failed".to_string())
+            }
+        },
+        Some(_) => Err("This server issues access tokens. \
+            Send your access token instead of a Hevy API key"
+            .to_string()),
+        None if is_token(&credential) => {
+            Err("This server does not issue access tokens. Send a Hevy API key".to_string())
+        }
+        None => Ok((credential, None, source)),
+    }
+}
+
+fn client_credential(
+    state: &AppState,
+    query: &McpQuery,
+    headers: &HeaderMap,
+) -> Result<Option<(String, ApiKeySource)>, String> {
+    if let Some(key) = header_value(headers, HEVY_API_KEY_HEADER) {
+        return Ok(Some((key.to_string(), ApiKeySource::Header)));
+    }
+    if let Some(value) = header_value(headers, header::AUTHORIZATION.as_str()) {
+        return match bearer_token(value) {
+            Some(token) => Ok(Some((token.to_string(), ApiKeySource::Bearer))),
+            None => Err("Authorization header must have the form: Bearer <credential>".to_string()),
+        };
+    }
+    if let Some(key) = &query.apikey {
+        if !state.allow_query_api_key {
+            return Err("API keys in the query string are disabled on this server. \
+                Send the key in an Authorization: Bearer or X-Hevy-Api-Key header instead"
+                .to_string());
+        }
+        return Ok(Some((key.clone(), ApiKeySource::Query)));
+    }
+    Ok(None)
+}
+
+pub(crate) fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+pub(crate) fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
 async fn handle_notification(
     state: AppState,
     session_id: Option<String>,
@@ -195,6 +560,89 @@ async fn handle_notification(
     (StatusCode::ACCEPTED, "").into_response()
 }
 
+pub(crate) async fn sse_handler(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let session_id = match headers
+        .get(MCP_SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(id) => id.to_string(),
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Missing Mcp-Session-Id header".to_string(),
+            )
+                .into_response();
+        }
+    };
+
+    let last_event_id = match headers.get(LAST_EVENT_ID_HEADER) {
+        Some(value) => match value.to_str().ok().and_then(|v| v.trim().parse().ok()) {
+            Some(id) => Some(id),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    "Invalid Last-Event-ID header".to_string(),
+                )
+                    .into_response();
+            }
+        },
+        None => None,
+    };
+
+    let session = match state.session_manager.get_session(&session_id) {
+        Some(session) => session,
+        None => return (StatusCode::NOT_FOUND, "Session not found".to_string()).into_response(),
+    };
+    if let Err(message) =
+        check_protocol_version(protocol_version_header(&headers).as_deref(), &session)
+    {
+        return (StatusCode::BAD_REQUEST, message).into_response();
+    }
+    state.session_manager.update_activity(&session_id);
+
+    // Resume the stream the last event was sent on. Events that have aged out
+    // of the log can no longer be placed, so those resume the standalone stream.
+    // Signed sessions move between replicas, and an event this one never sent
+    // cannot be resumed here.
+    let stream = match last_event_id.map(|id| (id, session.events.stream_of(id))) {
+        Some((_, Some(stream))) => stream,
+        Some((id, None)) if session.signed.is_some() => {
+            return (
+                StatusCode::NOT_FOUND,
+                format!(
+                    "Event {} was not sent by this server; event streams can only be resumed on the replica that sent them",
+                    id
+                ),
+            )
+                .into_response();
+        }
+        _ => STANDALONE_STREAM,
+    };
+    let body = session.events.subscribe(stream, last_event_id);
+    event_stream_response(state, session_id, body)
+}
+
+fn event_stream_response(
+    state: AppState,
+    session_id: String,
+    events: impl Stream<Item = SessionEvent> + Send + 'static,
+) -> Response {
+    let session_manager = state.session_manager;
+    let stream = events.map(move |event| {
+        session_manager.update_activity(&session_id);
+        Ok::<_, Infallible>(
+            Event::default()
+                .id(event.id.to_string())
+                .event("message")
+                .data(event.data),
+        )
+    });
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
 pub(crate) async fn delete_session(State(state): State<AppState>, headers: HeaderMap) -> Response {
     let session_id = match headers
         .get(MCP_SESSION_HEADER)
@@ -210,6 +658,13 @@ pub(crate) async fn delete_session(State(state): State<AppState>, headers: Heade
         }
     };
 
+    if let Some(session) = state.session_manager.get_session(session_id)
+        && let Err(message) =
+            check_protocol_version(protocol_version_header(&headers).as_deref(), &session)
+    {
+        return (StatusCode::BAD_REQUEST, message).into_response();
+    }
+
     if state.session_manager.remove_session(session_id) {
         StatusCode::NO_CONTENT.into_response()
     } else {
@@ -235,6 +690,32 @@ fn error_response(code: i32, message: String) -> Response {
         .into_response()
 }
 
+/// A 429 carrying the JSON-RPC error, with `Retry-After` when the wait is known.
+fn limit_response(id: Option<u64>, message: String, retry_after: Option<Duration>) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+    if let Some(retry_after) = retry_after {
+        headers.insert(header::RETRY_AFTER, retry_after_secs(retry_after).into());
+    }
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        headers,
+        json_body(error_json(id, LIMIT_EXCEEDED, message)),
+    )
+        .into_response()
+}
+
+fn tool_call_limit_message(retry_after: Duration) -> String {
+    format!(
+        "Tool call limit for this session reached. Try again in {} seconds",
+        retry_after_secs(retry_after)
+    )
+}
+
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    (retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)).max(1)
+}
+
 fn error_json(id: Option<u64>, code: i32, message: String) -> JsonRpcMessage {
     JsonRpcMessage::Error(JsonRpcError {
         jsonrpc: "2.0".to_string(),
@@ -1,20 +1,31 @@
 use std::env;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
-    Router,
-    routing::{delete, post},
+    Router, middleware,
+    routing::{delete, get, post},
     serve as axum_serve,
 };
 use thiserror::Error;
 use tokio::net::TcpListener;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::HevyRouter;
+use crate::tokens::TokenStore;
 
+mod admin;
+mod events;
 mod handler;
+mod oauth;
 mod session;
+mod stateless;
+mod store;
+
+const DEFAULT_MAX_SESSIONS: usize = 10_000;
+const DEFAULT_SESSION_TOKEN_LIFETIME_SECS: u64 = 24 * 60 * 60;
 
 pub async fn serve(router: HevyRouter, addr: SocketAddr) -> Result<(), HttpError> {
     let session_timeout_secs = env::var("HEVY_SESSION_TIMEOUT_SECS")
@@ -22,13 +33,126 @@ pub async fn serve(router: HevyRouter, addr: SocketAddr) -> Result<(), HttpError
         .and_then(|s| s.parse().ok())
         .unwrap_or(3600);
 
-    let session_manager = Arc::new(session::SessionManager::new(session_timeout_secs));
+    let session_store: Arc<dyn store::SessionStore> = match env::var("HEVY_SESSION_STORE") {
+        Ok(path) if !path.trim().is_empty() => {
+            let master_key = env::var("HEVY_MASTER_KEY").map_err(|_| {
+                HttpError::Config(
+                    "HEVY_MASTER_KEY is required when HEVY_SESSION_STORE is set".to_string(),
+                )
+            })?;
+            info!("Persisting sessions to {}", path.trim());
+            Arc::new(
+                store::FileSessionStore::open(path.trim(), &master_key)
+                    .map_err(|err| HttpError::Config(err.to_string()))?,
+            )
+        }
+        _ => Arc::new(store::MemorySessionStore),
+    };
+    let limits = session::SessionLimits {
+        max_sessions: limit_var("HEVY_MAX_SESSIONS", Some(DEFAULT_MAX_SESSIONS))?,
+        max_sessions_per_key: limit_var("HEVY_MAX_SESSIONS_PER_KEY", None)?,
+        tool_calls_per_minute: limit_var("HEVY_TOOL_CALLS_PER_MINUTE", None)?,
+    };
+    let tokens = TokenStore::from_env()
+        .map_err(|err| HttpError::Config(err.to_string()))?
+        .map(Arc::new);
+    if tokens.is_some() {
+        info!("Access tokens enabled - clients send server-issued tokens instead of API keys");
+    }
+    let mut session_manager = session::SessionManager::new(session_timeout_secs)
+        .with_store(session_store)
+        .with_limits(limits);
+    if let Some(tokens) = &tokens {
+        session_manager = session_manager.with_tokens(tokens.clone());
+    }
+
+    if let Ok(secret) = env::var("HEVY_SESSION_SECRET")
+        && !secret.trim().is_empty()
+    {
+        if env::var("HEVY_SESSION_STORE").is_ok_and(|path| !path.trim().is_empty()) {
+            return Err(HttpError::Config(
+                "HEVY_SESSION_SECRET cannot be combined with HEVY_SESSION_STORE".to_string(),
+            ));
+        }
+        // Pending confirmations live on the replica that showed the preview.
+        if router.has_confirmation_tools() {
+            return Err(HttpError::Config(
+                "HEVY_CONFIRM_TOOLS cannot be combined with HEVY_SESSION_SECRET".to_string(),
+            ));
+        }
+        let master_key = env::var("HEVY_MASTER_KEY").map_err(|_| {
+            HttpError::Config(
+                "HEVY_MASTER_KEY is required when HEVY_SESSION_SECRET is set".to_string(),
+            )
+        })?;
+        let token_lifetime_secs = match env::var("HEVY_SESSION_TOKEN_LIFETIME_SECS") {
+            Ok(value) => match value.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => secs,
+                _ => {
+                    return Err(HttpError::Config(format!(
+                        "HEVY_SESSION_TOKEN_LIFETIME_SECS must be a positive number of seconds, got {}",
+                        value
+                    )));
+                }
+            },
+            Err(_) => DEFAULT_SESSION_TOKEN_LIFETIME_SECS,
+        };
+        let tokens = stateless::SessionTokens::new(
+            secret.trim(),
+            &master_key,
+            Duration::from_secs(token_lifetime_secs),
+        )
+        .map_err(|err| HttpError::Config(err.to_string()))?;
+        let deny_list_path = env::var("HEVY_SESSION_DENY_LIST")
+            .ok()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty());
+        match &deny_list_path {
+            Some(path) => info!("Issuing signed session tokens, revocations in {}", path),
+            None => info!("Issuing signed session tokens, revocations kept in memory"),
+        }
+        if limits.max_sessions_per_key.is_some() || limits.tool_calls_per_minute.is_some() {
+            warn!("Session and tool call limits are enforced by each replica separately");
+        }
+        session_manager = session_manager.with_stateless(session::StatelessSessions {
+            tokens,
+            deny_list: stateless::DenyList::new(deny_list_path.map(PathBuf::from)),
+            router: router.clone(),
+        });
+    }
+    session_manager
+        .restore(&router)
+        .and_then(|_| session_manager.reload_deny_list())
+        .map_err(|err| HttpError::Config(err.to_string()))?;
+    let session_manager = Arc::new(session_manager);
     session_manager.clone().start_cleanup_task();
 
-    let state = handler::AppState {
-        router: Arc::new(router),
-        session_manager,
+    let allow_query_api_key = match env::var("HEVY_ALLOW_QUERY_API_KEY") {
+        Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "" | "1" | "true" | "yes" => true,
+            "0" | "false" | "no" => false,
+            _ => {
+                return Err(HttpError::Config(format!(
+                    "HEVY_ALLOW_QUERY_API_KEY must be true or false, got {}",
+                    value
+                )));
+            }
+        },
+        Err(_) => true,
+    };
+    if !allow_query_api_key {
+        info!("API keys in the query string are disabled");
+    }
+
+    let admin_token = match env::var("HEVY_ADMIN_TOKEN") {
+        Ok(value) if !value.trim().is_empty() => Some(value.trim().to_string()),
+        _ => None,
     };
+    if admin_token.is_some() && tokens.is_none() {
+        return Err(HttpError::Config(
+            "HEVY_ADMIN_TOKEN requires HEVY_TOKEN_STORE".to_string(),
+        ));
+    }
 
     let path = match env::var("HEVY_MCP_PATH") {
         Ok(p) if p.starts_with('/') => p,
@@ -36,10 +160,58 @@ pub async fn serve(router: HevyRouter, addr: SocketAddr) -> Result<(), HttpError
         Err(_) => "/".to_string(),
     };
 
-    let app = Router::new()
+    let oauth = match env::var("HEVY_OAUTH_ISSUER") {
+        Ok(issuer) if !issuer.trim().is_empty() => {
+            let issuer = issuer.trim();
+            if !issuer.starts_with("https://") && !issuer.starts_with("http://") {
+                return Err(HttpError::Config(format!(
+                    "HEVY_OAUTH_ISSUER must be the server's public http(s) URL, got {}",
+                    issuer
+                )));
+            }
+            if router.default_api_key.is_some() || tokens.is_some() {
+                return Err(HttpError::Config(
+                    "HEVY_OAUTH_ISSUER cannot be combined with HEVY_API_KEY or HEVY_TOKEN_STORE"
+                        .to_string(),
+                ));
+            }
+            info!(
+                "OAuth enabled - every MCP request needs an access token from {}",
+                issuer
+            );
+            Some(Arc::new(oauth::OAuthServer::new(
+                issuer,
+                &path,
+                router.http_client().clone(),
+            )))
+        }
+        _ => None,
+    };
+
+    let state = handler::AppState {
+        router: Arc::new(router),
+        session_manager,
+        allow_query_api_key,
+        tokens: tokens.clone(),
+        oauth: oauth.clone(),
+    };
+
+    let mut app = Router::new()
         .route(&path, post(handler::mcp_handler))
+        .route(&path, get(handler::sse_handler))
         .route(&path, delete(handler::delete_session))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            oauth::require_access_token,
+        ))
         .with_state(state);
+    if let (Some(tokens), Some(admin_token)) = (tokens, admin_token) {
+        info!("Token admin endpoints enabled");
+        app = app.merge(admin::routes(&path, tokens, admin_token));
+    }
+    if let Some(oauth) = oauth {
+        app = app.merge(oauth.routes());
+    }
 
     let listener = TcpListener::bind(addr)
         .await
@@ -51,8 +223,30 @@ pub async fn serve(router: HevyRouter, addr: SocketAddr) -> Result<(), HttpError
     Ok(())
 }
 
+/// A limit from the environment, where `0` turns it off.
+fn limit_var(name: &str, default: Option<usize>) -> Result<Option<usize>, HttpError> {
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse::<usize>()
+            .map(|limit| Some(limit).filter(|limit| *limit > 0))
+            .map_err(|_| {
+                HttpError::Config(format!(
+                    "{} must be a number, or 0 for no limit, got {}",
+                    name, value
+                ))
+            }),
+        Err(env::VarError::NotPresent) => Ok(default),
+        Err(env::VarError::NotUnicode(_)) => {
+            Err(HttpError::Config(format!("{} must be valid UTF-8", name)))
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum HttpError {
+    #[error("{0}")]
+    Config(String),
     #[error("failed to bind HTTP listener: {0}")]
     Bind(String),
     #[error("HTTP server error: {0}")]
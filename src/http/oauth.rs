@@ -0,0 +1,1048 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{
+    Form, Json, Router,
+    extract::{Query, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use dashmap::DashMap;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tracing::info;
+use uuid::Uuid;
+
+use super::handler::{AppState, bearer_token, header_value, session_id_header};
+use super::session::RateWindow;
+use crate::HevyClient;
+use crate::hevy::client::HevyError;
+use crate::tokens::{fingerprint, random_token};
+
+const ACCESS_TOKEN_PREFIX: &str = "hevy_oat_";
+const REFRESH_TOKEN_PREFIX: &str = "hevy_ort_";
+const ACCESS_TOKEN_TTL: Duration = Duration::from_secs(60 * 60);
+const REFRESH_TOKEN_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const AUTHORIZATION_CODE_TTL: Duration = Duration::from_secs(10 * 60);
+/// How long a registered client has to complete its first authorization.
+const PENDING_CLIENT_TTL: Duration = Duration::from_secs(60 * 60);
+/// Registration needs no credentials, so only this many clients may be
+/// waiting for their first authorization; the oldest make way for new ones.
+const MAX_PENDING_CLIENTS: usize = 1_000;
+/// The approval form checks pasted keys against Hevy without any other
+/// credentials, so only this many checks run per minute across all clients.
+const MAX_KEY_CHECKS_PER_MINUTE: usize = 20;
+const PROTECTED_RESOURCE_PATH: &str = "/.well-known/oauth-protected-resource";
+
+/// The Hevy API key behind the access token on the current request.
+#[derive(Clone)]
+pub(crate) struct OAuthApiKey(pub(crate) String);
+
+struct RegisteredClient {
+    name: Option<String>,
+    redirect_uris: Vec<String>,
+    registered_at: SystemTime,
+    /// When tokens were last issued to the client, if ever.
+    authorized_at: Option<SystemTime>,
+}
+
+impl RegisteredClient {
+    /// Clients that never authorized expire quickly; the others once their
+    /// last refresh token would have.
+    fn is_expired(&self, now: SystemTime) -> bool {
+        let (since, ttl) = match self.authorized_at {
+            Some(authorized_at) => (authorized_at, REFRESH_TOKEN_TTL),
+            None => (self.registered_at, PENDING_CLIENT_TTL),
+        };
+        now.duration_since(since).unwrap_or_default() >= ttl
+    }
+}
+
+struct AuthorizationCode {
+    client_id: String,
+    redirect_uri: String,
+    code_challenge: String,
+    api_key: String,
+    expires_at: SystemTime,
+}
+
+struct Grant {
+    client_id: String,
+    api_key: String,
+    expires_at: SystemTime,
+}
+
+/// An OAuth 2.1 authorization server for the MCP endpoint. Clients register
+/// themselves, the user approves them by pasting a Hevy API key, and the
+/// issued tokens map back to that key. Everything lives in memory, so tokens
+/// do not survive a restart. Codes and tokens are stored by fingerprint only.
+pub(crate) struct OAuthServer {
+    issuer: String,
+    resource: String,
+    metadata_path: String,
+    http_client: reqwest::Client,
+    clients: DashMap<String, RegisteredClient>,
+    codes: DashMap<String, AuthorizationCode>,
+    access_tokens: DashMap<String, Grant>,
+    refresh_tokens: DashMap<String, Grant>,
+    key_checks: RateWindow,
+}
+
+impl OAuthServer {
+    pub(crate) fn new(issuer: &str, mcp_path: &str, http_client: reqwest::Client) -> Self {
+        let issuer = issuer.trim_end_matches('/').to_string();
+        let metadata_path = match mcp_path {
+            "/" => PROTECTED_RESOURCE_PATH.to_string(),
+            path => format!("{PROTECTED_RESOURCE_PATH}{path}"),
+        };
+        Self {
+            resource: format!("{issuer}{mcp_path}"),
+            issuer,
+            metadata_path,
+            http_client,
+            clients: DashMap::new(),
+            codes: DashMap::new(),
+            access_tokens: DashMap::new(),
+            refresh_tokens: DashMap::new(),
+            key_checks: RateWindow::default(),
+        }
+    }
+
+    pub(crate) fn routes(self: Arc<Self>) -> Router {
+        let mut router = Router::new()
+            .route(PROTECTED_RESOURCE_PATH, get(protected_resource_metadata))
+            .route(
+                "/.well-known/oauth-authorization-server",
+                get(authorization_server_metadata),
+            )
+            .route("/register", post(register_client))
+            .route("/authorize", get(authorize_page).post(approve))
+            .route("/token", post(token));
+        if self.metadata_path != PROTECTED_RESOURCE_PATH {
+            router = router.route(&self.metadata_path, get(protected_resource_metadata));
+        }
+        router.with_state(self)
+    }
+
+    /// The Hevy API key for a live access token.
+    fn resolve(&self, access_token: &str) -> Option<String> {
+        let grant = self.access_tokens.get(&fingerprint(access_token))?;
+        (grant.expires_at > SystemTime::now()).then(|| grant.api_key.clone())
+    }
+
+    /// A 401 pointing the client at the metadata it needs to start authorization.
+    fn challenge(&self, invalid_token: bool) -> Response {
+        let mut challenge = format!(
+            "Bearer resource_metadata=\"{}{}\"",
+            self.issuer, self.metadata_path
+        );
+        let message = if invalid_token {
+            challenge.push_str(", error=\"invalid_token\"");
+            "The access token is invalid or expired"
+        } else {
+            "Authorization required"
+        };
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, challenge)],
+            Json(json!({ "error": message })),
+        )
+            .into_response()
+    }
+
+    fn issue_tokens(&self, client_id: String, api_key: String) -> Value {
+        let now = SystemTime::now();
+        if let Some(mut client) = self.clients.get_mut(&client_id) {
+            client.authorized_at = Some(now);
+        }
+        self.access_tokens.retain(|_, grant| grant.expires_at > now);
+        self.refresh_tokens
+            .retain(|_, grant| grant.expires_at > now);
+
+        let access_token = random_token(ACCESS_TOKEN_PREFIX);
+        let refresh_token = random_token(REFRESH_TOKEN_PREFIX);
+        self.access_tokens.insert(
+            fingerprint(&access_token),
+            Grant {
+                client_id: client_id.clone(),
+                api_key: api_key.clone(),
+                expires_at: now + ACCESS_TOKEN_TTL,
+            },
+        );
+        self.refresh_tokens.insert(
+            fingerprint(&refresh_token),
+            Grant {
+                client_id,
+                api_key,
+                expires_at: now + REFRESH_TOKEN_TTL,
+            },
+        );
+        json!({
+            "access_token": access_token,
+            "token_type": "Bearer",
+            "expires_in": ACCESS_TOKEN_TTL.as_secs(),
+            "refresh_token": refresh_token,
+        })
+    }
+
+    fn exchange_code(&self, request: &TokenRequest) -> Result<Value, TokenRejection> {
+        let code = required(&request.code, "code")?;
+        let Some((_, pending)) = self.codes.remove(&fingerprint(code)) else {
+            return Err(invalid_grant(
+                "The authorization code is unknown or was already used",
+            ));
+        };
+        if pending.expires_at <= SystemTime::now() {
+            return Err(invalid_grant("The authorization code has expired"));
+        }
+        if request.client_id.as_deref() != Some(pending.client_id.as_str()) {
+            return Err(invalid_grant(
+                "The authorization code was issued to another client",
+            ));
+        }
+        if request
+            .redirect_uri
+            .as_deref()
+            .is_some_and(|uri| uri != pending.redirect_uri)
+        {
+            return Err(invalid_grant(
+                "redirect_uri does not match the authorization request",
+            ));
+        }
+        let verifier = required(&request.code_verifier, "code_verifier")?;
+        if URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) != pending.code_challenge {
+            return Err(invalid_grant(
+                "code_verifier does not match the code challenge",
+            ));
+        }
+        Ok(self.issue_tokens(pending.client_id, pending.api_key))
+    }
+
+    /// Refresh tokens are single use; every refresh returns a new one.
+    fn refresh(&self, request: &TokenRequest) -> Result<Value, TokenRejection> {
+        let refresh_token = required(&request.refresh_token, "refresh_token")?;
+        let Some((_, grant)) = self.refresh_tokens.remove(&fingerprint(refresh_token)) else {
+            return Err(invalid_grant(
+                "The refresh token is unknown or was already used",
+            ));
+        };
+        if grant.expires_at <= SystemTime::now() {
+            return Err(invalid_grant("The refresh token has expired"));
+        }
+        if request
+            .client_id
+            .as_deref()
+            .is_some_and(|client_id| client_id != grant.client_id)
+        {
+            return Err(invalid_grant(
+                "The refresh token was issued to another client",
+            ));
+        }
+        Ok(self.issue_tokens(grant.client_id, grant.api_key))
+    }
+
+    /// A single-use code the client exchanges for tokens mapping to `api_key`.
+    fn issue_code(&self, authorization: &Authorization, api_key: &str) -> String {
+        let now = SystemTime::now();
+        self.codes.retain(|_, code| code.expires_at > now);
+        let code = random_token("");
+        self.codes.insert(
+            fingerprint(&code),
+            AuthorizationCode {
+                client_id: authorization.client_id.clone(),
+                redirect_uri: authorization.redirect_uri.clone(),
+                code_challenge: authorization.code_challenge.clone(),
+                api_key: api_key.to_string(),
+                expires_at: now + AUTHORIZATION_CODE_TTL,
+            },
+        );
+        code
+    }
+
+    /// Registers a client, dropping the oldest ones still waiting for their
+    /// first authorization if there are too many.
+    fn register(&self, name: Option<String>, redirect_uris: Vec<String>) -> String {
+        self.prune_clients();
+        let now = SystemTime::now();
+        let mut pending: Vec<(SystemTime, String)> = self
+            .clients
+            .iter()
+            .filter(|client| client.authorized_at.is_none())
+            .map(|client| (client.registered_at, client.key().clone()))
+            .collect();
+        let excess = (pending.len() + 1).saturating_sub(MAX_PENDING_CLIENTS);
+        if excess > 0 {
+            pending.sort();
+            for (_, client_id) in pending.into_iter().take(excess) {
+                self.clients
+                    .remove_if(&client_id, |_, client| client.authorized_at.is_none());
+            }
+        }
+
+        let client_id = Uuid::new_v4().to_string();
+        self.clients.insert(
+            client_id.clone(),
+            RegisteredClient {
+                name,
+                redirect_uris,
+                registered_at: now,
+                authorized_at: None,
+            },
+        );
+        client_id
+    }
+
+    fn prune_clients(&self) {
+        let now = SystemTime::now();
+        self.clients.retain(|_, client| !client.is_expired(now));
+    }
+
+    /// Checks an authorization request. Problems with the client or its
+    /// redirect URI are shown to the user, because redirecting to an unverified
+    /// URI is unsafe; anything else goes back to the client in the redirect.
+    fn validate(&self, params: &AuthorizeParams) -> Result<Authorization, AuthorizeError> {
+        self.prune_clients();
+        let client_id = params.client_id.as_deref().unwrap_or_default();
+        let Some(client) = self.clients.get(client_id) else {
+            return Err(AuthorizeError::Page(
+                "Unknown client. The MCP client has to register before it can ask for access."
+                    .to_string(),
+            ));
+        };
+        let redirect_uri = match (&params.redirect_uri, &client.redirect_uris[..]) {
+            (Some(uri), registered) if registered.contains(uri) => uri.clone(),
+            (None, [only]) => only.clone(),
+            _ => {
+                return Err(AuthorizeError::Page(
+                    "The redirect_uri was not registered by this client.".to_string(),
+                ));
+            }
+        };
+
+        let reject = |error, description: &str| {
+            Err(AuthorizeError::Redirect {
+                redirect_uri: redirect_uri.clone(),
+                state: params.state.clone(),
+                error,
+                description: description.to_string(),
+            })
+        };
+        if params.response_type.as_deref() != Some("code") {
+            return reject(
+                "unsupported_response_type",
+                "Only response_type=code is supported",
+            );
+        }
+        let Some(code_challenge) = params.code_challenge.clone() else {
+            return reject("invalid_request", "A PKCE code_challenge is required");
+        };
+        if params.code_challenge_method.as_deref() != Some("S256") {
+            return reject("invalid_request", "code_challenge_method must be S256");
+        }
+        if params.resource.as_deref().is_some_and(|resource| {
+            resource.trim_end_matches('/') != self.resource.trim_end_matches('/')
+        }) {
+            return reject(
+                "invalid_target",
+                "Tokens can only be issued for this MCP server",
+            );
+        }
+
+        Ok(Authorization {
+            client_id: client_id.to_string(),
+            client_name: client.name.clone(),
+            redirect_uri,
+            code_challenge,
+        })
+    }
+}
+
+/// Rejects MCP requests without a live access token and hands the Hevy key it
+/// maps to on to the handlers.
+pub(crate) async fn require_access_token(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(oauth) = state.oauth.clone() else {
+        return next.run(request).await;
+    };
+    let headers = request.headers();
+    let Some(access_token) =
+        header_value(headers, header::AUTHORIZATION.as_str()).and_then(bearer_token)
+    else {
+        return oauth.challenge(false);
+    };
+    let Some(api_key) = oauth.resolve(access_token) else {
+        return oauth.challenge(true);
+    };
+    // A session stays bound to the Hevy account that created it.
+    if let Some(session) =
+        session_id_header(headers).and_then(|id| state.session_manager.get_session(&id))
+        && session.client_lease.key_id() != fingerprint(&api_key)
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            "The access token belongs to a different Hevy account than this session",
+        )
+            .into_response();
+    }
+
+    request.extensions_mut().insert(OAuthApiKey(api_key));
+    next.run(request).await
+}
+
+async fn protected_resource_metadata(State(oauth): State<Arc<OAuthServer>>) -> Json<Value> {
+    Json(json!({
+        "resource": oauth.resource,
+        "authorization_servers": [oauth.issuer],
+        "bearer_methods_supported": ["header"],
+        "resource_name": "Hevy MCP Server",
+    }))
+}
+
+async fn authorization_server_metadata(State(oauth): State<Arc<OAuthServer>>) -> Json<Value> {
+    Json(json!({
+        "issuer": oauth.issuer,
+        "authorization_endpoint": format!("{}/authorize", oauth.issuer),
+        "token_endpoint": format!("{}/token", oauth.issuer),
+        "registration_endpoint": format!("{}/register", oauth.issuer),
+        "response_types_supported": ["code"],
+        "grant_types_supported": ["authorization_code", "refresh_token"],
+        "code_challenge_methods_supported": ["S256"],
+        "token_endpoint_auth_methods_supported": ["none"],
+        "authorization_response_iss_parameter_supported": true,
+    }))
+}
+
+#[derive(Deserialize)]
+struct RegistrationRequest {
+    #[serde(default)]
+    redirect_uris: Vec<String>,
+    client_name: Option<String>,
+}
+
+/// Dynamic client registration (RFC 7591) for public clients.
+async fn register_client(
+    State(oauth): State<Arc<OAuthServer>>,
+    Json(request): Json<RegistrationRequest>,
+) -> Response {
+    if request.redirect_uris.is_empty() {
+        return oauth_error(
+            "invalid_redirect_uri",
+            "redirect_uris must list at least one URI",
+        );
+    }
+    if let Some(uri) = request
+        .redirect_uris
+        .iter()
+        .find(|uri| !is_allowed_redirect_uri(uri))
+    {
+        return oauth_error(
+            "invalid_redirect_uri",
+            &format!(
+                "{} must be an https URL, a loopback http URL or an app-specific scheme",
+                uri
+            ),
+        );
+    }
+
+    let client_id = oauth.register(request.client_name.clone(), request.redirect_uris.clone());
+    info!(
+        "Registered OAuth client {} ({})",
+        client_id,
+        request.client_name.as_deref().unwrap_or("unnamed")
+    );
+    let issued_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "client_id": client_id,
+            "client_id_issued_at": issued_at,
+            "client_name": request.client_name,
+            "redirect_uris": request.redirect_uris,
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none",
+        })),
+    )
+        .into_response()
+}
+
+fn is_allowed_redirect_uri(uri: &str) -> bool {
+    let Ok(url) = Url::parse(uri) else {
+        return false;
+    };
+    if url.fragment().is_some() {
+        return false;
+    }
+    match url.scheme() {
+        "https" => true,
+        "http" => matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]")),
+        "javascript" | "data" | "file" | "vbscript" => false,
+        // Private-use schemes of native apps (RFC 8252).
+        _ => true,
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct AuthorizeParams {
+    response_type: Option<String>,
+    client_id: Option<String>,
+    redirect_uri: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
+    state: Option<String>,
+    resource: Option<String>,
+    scope: Option<String>,
+    /// Only set when the approval form is submitted.
+    api_key: Option<String>,
+}
+
+struct Authorization {
+    client_id: String,
+    client_name: Option<String>,
+    redirect_uri: String,
+    code_challenge: String,
+}
+
+enum AuthorizeError {
+    Page(String),
+    Redirect {
+        redirect_uri: String,
+        state: Option<String>,
+        error: &'static str,
+        description: String,
+    },
+}
+
+impl AuthorizeError {
+    fn into_response(self, issuer: &str) -> Response {
+        match self {
+            AuthorizeError::Page(message) => (
+                StatusCode::BAD_REQUEST,
+                page_headers(),
+                Html(page(&format!(
+                    "<p class=\"error\">{}</p>",
+                    escape(&message)
+                ))),
+            )
+                .into_response(),
+            AuthorizeError::Redirect {
+                redirect_uri,
+                state,
+                error,
+                description,
+            } => redirect_with(
+                &redirect_uri,
+                &[
+                    ("error", Some(error)),
+                    ("error_description", Some(&description)),
+                    ("state", state.as_deref()),
+                    ("iss", Some(issuer)),
+                ],
+            ),
+        }
+    }
+}
+
+async fn authorize_page(
+    State(oauth): State<Arc<OAuthServer>>,
+    Query(params): Query<AuthorizeParams>,
+) -> Response {
+    match oauth.validate(&params) {
+        Ok(authorization) => approval_form(&authorization, &params, None),
+        Err(err) => err.into_response(&oauth.issuer),
+    }
+}
+
+/// Handles the submitted approval form: checks the pasted key against Hevy and
+/// redirects back to the client with an authorization code.
+async fn approve(
+    State(oauth): State<Arc<OAuthServer>>,
+    Form(params): Form<AuthorizeParams>,
+) -> Response {
+    let authorization = match oauth.validate(&params) {
+        Ok(authorization) => authorization,
+        Err(err) => return err.into_response(&oauth.issuer),
+    };
+    let api_key = params.api_key.as_deref().unwrap_or_default().trim();
+    if api_key.is_empty() {
+        return approval_form(&authorization, &params, Some("Enter your Hevy API key."));
+    }
+    if let Err(retry_after) = oauth.key_checks.try_acquire(MAX_KEY_CHECKS_PER_MINUTE) {
+        let retry_after = retry_after.as_secs().max(1);
+        let mut response = approval_form(
+            &authorization,
+            &params,
+            Some(&format!(
+                "Too many API keys were checked recently. Try again in {} seconds.",
+                retry_after
+            )),
+        );
+        *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, retry_after.into());
+        return response;
+    }
+    match HevyClient::with_http_client(oauth.http_client.clone(), api_key)
+        .get_workouts_count()
+        .await
+    {
+        Ok(_) => {}
+        Err(HevyError::Unauthorized) => {
+            return approval_form(&authorization, &params, Some("Hevy rejected this API key."));
+        }
+        Err(err) => {
+            return approval_form(
+                &authorization,
+                &params,
+                Some(&format!("Could not check the key with Hevy: {}", err)),
+            );
+        }
+    }
+
+    let code = oauth.issue_code(&authorization, api_key);
+    info!("Authorized OAuth client {}", authorization.client_id);
+    redirect_with(
+        &authorization.redirect_uri,
+        &[
+            ("code", Some(&code)),
+            ("state", params.state.as_deref()),
+            ("iss", Some(&oauth.issuer)),
+        ],
+    )
+}
+
+fn approval_form(
+    authorization: &Authorization,
+    params: &AuthorizeParams,
+    error: Option<&str>,
+) -> Response {
+    let hidden: String = [
+        ("response_type", params.response_type.as_deref()),
+        ("client_id", Some(authorization.client_id.as_str())),
+        ("redirect_uri", Some(authorization.redirect_uri.as_str())),
+        ("code_challenge", params.code_challenge.as_deref()),
+        (
+            "code_challenge_method",
+            params.code_challenge_method.as_deref(),
+        ),
+        ("state", params.state.as_deref()),
+        ("resource", params.resource.as_deref()),
+        ("scope", params.scope.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| {
+        value.map(|value| {
+            format!(
+                "<input type=\"hidden\" name=\"{}\" value=\"{}\">",
+                name,
+                escape(value)
+            )
+        })
+    })
+    .collect();
+    let error = error
+        .map(|message| format!("<p class=\"error\">{}</p>", escape(message)))
+        .unwrap_or_default();
+    // Clients register themselves, so their name proves nothing; where the
+    // code is sent does.
+    let client = match &authorization.client_name {
+        Some(name) => format!(
+            "An MCP client calling itself <strong>{}</strong>",
+            escape(name)
+        ),
+        None => "An MCP client".to_string(),
+    };
+    let body = format!(
+        "<p>{} wants to use your Hevy account through this server.</p>\
+        <p>If you allow it, you will be sent back to <strong>{}</strong>. Only continue \
+        if you started this from an app you trust there.</p>\
+        <p>Paste your Hevy API key to allow it. You can find the key in the Hevy app \
+        under Settings, Developer.</p>{}\
+        <form method=\"post\" action=\"authorize\">{}\
+        <input type=\"password\" name=\"api_key\" placeholder=\"Hevy API key\" \
+        autocomplete=\"off\" required autofocus>\
+        <button type=\"submit\">Allow</button></form>",
+        client,
+        escape(&redirect_target(&authorization.redirect_uri)),
+        error,
+        hidden
+    );
+    (StatusCode::OK, page_headers(), Html(page(&body))).into_response()
+}
+
+/// Where a redirect URI leads: the host for web URLs, and the scheme too for
+/// the private-use schemes of native apps, which any app may claim.
+fn redirect_target(redirect_uri: &str) -> String {
+    let Ok(url) = Url::parse(redirect_uri) else {
+        return redirect_uri.to_string();
+    };
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => String::new(),
+    };
+    match url.scheme() {
+        "http" | "https" => host,
+        scheme if host.is_empty() => format!("{}:", scheme),
+        scheme => format!("{}://{}", scheme, host),
+    }
+}
+
+fn page(body: &str) -> String {
+    format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\">\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+        <title>Authorize - Hevy MCP Server</title><style>\
+        body{{font-family:system-ui,sans-serif;max-width:28rem;margin:3rem auto;padding:0 1rem}}\
+        input,button{{font:inherit;padding:.5rem;width:100%;box-sizing:border-box;margin:.25rem 0}}\
+        .error{{color:#b00020}}</style></head><body><h1>Hevy MCP Server</h1>{}</body></html>",
+        body
+    )
+}
+
+/// Keeps the key form out of frames and caches.
+fn page_headers() -> [(header::HeaderName, &'static str); 3] {
+    [
+        (header::X_FRAME_OPTIONS, "DENY"),
+        (header::CONTENT_SECURITY_POLICY, "frame-ancestors 'none'"),
+        (header::CACHE_CONTROL, "no-store"),
+    ]
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn redirect_with(redirect_uri: &str, params: &[(&str, Option<&str>)]) -> Response {
+    let Ok(mut url) = Url::parse(redirect_uri) else {
+        return (StatusCode::BAD_REQUEST, "Invalid redirect_uri").into_response();
+    };
+    {
+        let mut query = url.query_pairs_mut();
+        for (name, value) in params {
+            if let Some(value) = value {
+                query.append_pair(name, value);
+            }
+        }
+    }
+    Redirect::to(url.as_str()).into_response()
+}
+
+#[derive(Default, Deserialize)]
+struct TokenRequest {
+    grant_type: Option<String>,
+    code: Option<String>,
+    redirect_uri: Option<String>,
+    client_id: Option<String>,
+    code_verifier: Option<String>,
+    refresh_token: Option<String>,
+}
+
+struct TokenRejection {
+    error: &'static str,
+    description: String,
+}
+
+async fn token(
+    State(oauth): State<Arc<OAuthServer>>,
+    Form(request): Form<TokenRequest>,
+) -> Response {
+    let result = match request.grant_type.as_deref() {
+        Some("authorization_code") => oauth.exchange_code(&request),
+        Some("refresh_token") => oauth.refresh(&request),
+        Some(_) => Err(TokenRejection {
+            error: "unsupported_grant_type",
+            description: "grant_type must be authorization_code or refresh_token".to_string(),
+        }),
+        None => Err(TokenRejection {
+            error: "invalid_request",
+            description: "grant_type is required".to_string(),
+        }),
+    };
+    match result {
+        Ok(tokens) => (
+            StatusCode::OK,
+            [(header::CACHE_CONTROL, "no-store")],
+            Json(tokens),
+        )
+            .into_response(),
+        Err(rejection) => oauth_error(rejection.error, &rejection.description),
+    }
+}
+
+fn required<'a>(value: &'a Option<String>, name: &str) -> Result<&'a str, TokenRejection> {
+    value.as_deref().ok_or_else(|| TokenRejection {
+        error: "invalid_request",
+        description: format!("{} is required", name),
+    })
+}
+
+fn invalid_grant(description: &str) -> TokenRejection {
+    TokenRejection {
+        error: "invalid_grant",
+        description: description.to_string(),
+    }
+}
+
+fn oauth_error(error: &str, description: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        [(header::CACHE_CONTROL, "no-store")],
+        Json(json!({ "error": error, "error_description": description })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REDIRECT_URI: &str = "http://localhost:3000/callback";
+    const VERIFIER: &str = "a-code-verifier-that-is-long-enough-for-pkce-purposes";
+
+    fn server() -> OAuthServer {
+        OAuthServer::new("https://hevy.example.com", "/mcp", reqwest::Client::new())
+    }
+
+    fn challenge(verifier: &str) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+    }
+
+    fn authorize_params(client_id: &str) -> AuthorizeParams {
+        AuthorizeParams {
+            response_type: Some("code".to_string()),
+            client_id: Some(client_id.to_string()),
+            redirect_uri: Some(REDIRECT_URI.to_string()),
+            code_challenge: Some(challenge(VERIFIER)),
+            code_challenge_method: Some("S256".to_string()),
+            ..AuthorizeParams::default()
+        }
+    }
+
+    /// Registers a client and approves it, returning its id and a code.
+    fn approved(oauth: &OAuthServer) -> (String, String) {
+        let client_id = oauth.register(Some("test".to_string()), vec![REDIRECT_URI.to_string()]);
+        let Ok(authorization) = oauth.validate(&authorize_params(&client_id)) else {
+            panic!("the authorization request should be valid");
+        };
+        let code = oauth.issue_code(&authorization, "hevy-api-key");
+        (client_id, code)
+    }
+
+    fn code_request(client_id: &str, code: &str, verifier: &str) -> TokenRequest {
+        TokenRequest {
+            grant_type: Some("authorization_code".to_string()),
+            code: Some(code.to_string()),
+            redirect_uri: Some(REDIRECT_URI.to_string()),
+            client_id: Some(client_id.to_string()),
+            code_verifier: Some(verifier.to_string()),
+            ..TokenRequest::default()
+        }
+    }
+
+    fn rejection(result: Result<Value, TokenRejection>) -> String {
+        match result {
+            Ok(tokens) => panic!("expected a rejection, got {}", tokens),
+            Err(rejection) => format!("{}: {}", rejection.error, rejection.description),
+        }
+    }
+
+    #[test]
+    fn code_with_the_right_verifier_gets_tokens_for_the_key() {
+        let oauth = server();
+        let (client_id, code) = approved(&oauth);
+        let Ok(tokens) = oauth.exchange_code(&code_request(&client_id, &code, VERIFIER)) else {
+            panic!("the code exchange should succeed");
+        };
+        let access_token = tokens["access_token"].as_str().unwrap();
+        assert_eq!(oauth.resolve(access_token).as_deref(), Some("hevy-api-key"));
+        assert!(
+            oauth
+                .clients
+                .get(&client_id)
+                .unwrap()
+                .authorized_at
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn wrong_pkce_verifier_is_rejected_and_uses_up_the_code() {
+        let oauth = server();
+        let (client_id, code) = approved(&oauth);
+        let message = rejection(oauth.exchange_code(&code_request(&client_id, &code, "wrong")));
+        assert!(
+            message.contains("code_verifier does not match"),
+            "{}",
+            message
+        );
+
+        let message = rejection(oauth.exchange_code(&code_request(&client_id, &code, VERIFIER)));
+        assert!(message.contains("already used"), "{}", message);
+    }
+
+    #[test]
+    fn code_from_another_client_is_rejected() {
+        let oauth = server();
+        let (_, code) = approved(&oauth);
+        let (other_client_id, _) = approved(&oauth);
+        let message =
+            rejection(oauth.exchange_code(&code_request(&other_client_id, &code, VERIFIER)));
+        assert!(message.contains("issued to another client"), "{}", message);
+    }
+
+    #[test]
+    fn code_for_another_redirect_uri_is_rejected() {
+        let oauth = server();
+        let (client_id, code) = approved(&oauth);
+        let mut request = code_request(&client_id, &code, VERIFIER);
+        request.redirect_uri = Some("http://localhost:4000/callback".to_string());
+        let message = rejection(oauth.exchange_code(&request));
+        assert!(
+            message.contains("redirect_uri does not match"),
+            "{}",
+            message
+        );
+    }
+
+    /// Tokens from a completed authorization, with the client they were issued to.
+    fn authorized(oauth: &OAuthServer) -> (String, Value) {
+        let (client_id, code) = approved(oauth);
+        let Ok(tokens) = oauth.exchange_code(&code_request(&client_id, &code, VERIFIER)) else {
+            panic!("the code exchange should succeed");
+        };
+        (client_id, tokens)
+    }
+
+    fn refresh_request(client_id: &str, tokens: &Value) -> TokenRequest {
+        TokenRequest {
+            grant_type: Some("refresh_token".to_string()),
+            client_id: Some(client_id.to_string()),
+            refresh_token: tokens["refresh_token"].as_str().map(str::to_string),
+            ..TokenRequest::default()
+        }
+    }
+
+    #[test]
+    fn refresh_token_is_single_use() {
+        let oauth = server();
+        let (client_id, tokens) = authorized(&oauth);
+        let request = refresh_request(&client_id, &tokens);
+        assert!(oauth.refresh(&request).is_ok());
+        let message = rejection(oauth.refresh(&request));
+        assert!(message.contains("already used"), "{}", message);
+    }
+
+    #[test]
+    fn refresh_token_from_another_client_is_rejected() {
+        let oauth = server();
+        let (_, tokens) = authorized(&oauth);
+        let (other_client_id, _) = authorized(&oauth);
+        let message = rejection(oauth.refresh(&refresh_request(&other_client_id, &tokens)));
+        assert!(message.contains("issued to another client"), "{}", message);
+    }
+
+    #[test]
+    fn unknown_client_cannot_authorize() {
+        let oauth = server();
+        assert!(matches!(
+            oauth.validate(&authorize_params("not-registered")),
+            Err(AuthorizeError::Page(_))
+        ));
+    }
+
+    #[test]
+    fn plain_pkce_is_refused() {
+        let oauth = server();
+        let client_id = oauth.register(None, vec![REDIRECT_URI.to_string()]);
+        let mut params = authorize_params(&client_id);
+        params.code_challenge_method = Some("plain".to_string());
+        assert!(matches!(
+            oauth.validate(&params),
+            Err(AuthorizeError::Redirect {
+                error: "invalid_request",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn clients_that_never_authorize_expire() {
+        let oauth = server();
+        let pending = oauth.register(None, vec![REDIRECT_URI.to_string()]);
+        let (authorized_id, _) = authorized(&oauth);
+
+        let long_ago = SystemTime::now() - PENDING_CLIENT_TTL - Duration::from_secs(1);
+        for mut client in oauth.clients.iter_mut() {
+            client.registered_at = long_ago;
+        }
+        assert!(matches!(
+            oauth.validate(&authorize_params(&pending)),
+            Err(AuthorizeError::Page(_))
+        ));
+        assert!(oauth.validate(&authorize_params(&authorized_id)).is_ok());
+    }
+
+    #[test]
+    fn oldest_pending_clients_make_way_for_new_ones() {
+        let oauth = server();
+        let (authorized_id, _) = authorized(&oauth);
+        let first = oauth.register(None, vec![REDIRECT_URI.to_string()]);
+        for _ in 0..MAX_PENDING_CLIENTS {
+            oauth.register(None, vec![REDIRECT_URI.to_string()]);
+        }
+        assert!(!oauth.clients.contains_key(&first));
+        assert!(oauth.clients.contains_key(&authorized_id));
+        assert_eq!(oauth.clients.len(), MAX_PENDING_CLIENTS + 1);
+    }
+
+    #[test]
+    fn consent_page_names_where_the_code_goes() {
+        assert_eq!(redirect_target(REDIRECT_URI), "localhost:3000");
+        assert_eq!(
+            redirect_target("https://app.example.com/oauth/callback"),
+            "app.example.com"
+        );
+        assert_eq!(
+            redirect_target("com.example.app:/oauth/callback"),
+            "com.example.app:"
+        );
+        assert_eq!(redirect_target("myapp://callback/path"), "myapp://callback");
+    }
+
+    #[tokio::test]
+    async fn key_checks_are_rate_limited() {
+        let oauth = Arc::new(server());
+        let client_id = oauth.register(None, vec![REDIRECT_URI.to_string()]);
+        for _ in 0..MAX_KEY_CHECKS_PER_MINUTE {
+            oauth
+                .key_checks
+                .try_acquire(MAX_KEY_CHECKS_PER_MINUTE)
+                .unwrap();
+        }
+        let params = AuthorizeParams {
+            api_key: Some("hevy-api-key".to_string()),
+            ..authorize_params(&client_id)
+        };
+        let response = approve(State(oauth), Form(params)).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+    }
+}
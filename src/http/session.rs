@@ -1,27 +1,135 @@
+use crate::HevyRouter;
+use crate::http::events::{EventLog, STANDALONE_STREAM};
+use crate::http::stateless::{DenyList, SessionTokens, SignedSession};
+use crate::http::store::{
+    MemorySessionStore, SessionCredential, SessionRecord, SessionStore, SessionStoreError,
+};
+use crate::router::{
+    ClientLease, ConfirmationStore, ProtocolVersion, ResourceSubscriptions, ToolPolicy,
+};
+use crate::tokens::TokenStore;
 use dashmap::DashMap;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
-use tokio::spawn;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use thiserror::Error;
+use tokio::task::{spawn, spawn_blocking};
 use tokio::time::sleep;
+use tracing::{info, warn};
 use uuid::Uuid;
 
+/// Where a session's Hevy API key came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeySource {
+    Header,
+    Bearer,
+    Query,
+    Token,
+    OAuth,
+    Default,
+}
+
+impl ApiKeySource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiKeySource::Header => "X-Hevy-Api-Key header",
+            ApiKeySource::Bearer => "Authorization header",
+            ApiKeySource::Query => "apikey query parameter",
+            ApiKeySource::Token => "server-issued access token",
+            ApiKeySource::OAuth => "OAuth access token",
+            ApiKeySource::Default => "HEVY_API_KEY",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SessionState {
     pub session_id: String,
-    pub api_key: String,
+    pub(crate) credential: SessionCredential,
+    pub api_key_source: ApiKeySource,
+    pub protocol_version: ProtocolVersion,
     pub last_activity: SystemTime,
+    pub(crate) confirmations: Arc<ConfirmationStore>,
+    pub policy: Arc<ToolPolicy>,
+    pub(crate) subscriptions: Arc<ResourceSubscriptions>,
+    pub(crate) events: Arc<EventLog>,
+    tool_calls: Arc<RateWindow>,
+    /// Set when the session id is a signed token rather than a local key.
+    pub(crate) signed: Option<SignedSession>,
+    /// Keeps the session's Hevy client cached for as long as the session lives.
+    pub(crate) client_lease: Arc<ClientLease>,
 }
 
 impl SessionState {
-    pub fn new(api_key: String) -> Self {
-        Self {
+    pub(crate) fn new(
+        credential: SessionCredential,
+        api_key_source: ApiKeySource,
+        policy: ToolPolicy,
+        protocol_version: ProtocolVersion,
+        client_lease: ClientLease,
+    ) -> Self {
+        let record = SessionRecord {
             session_id: Uuid::new_v4().to_string(),
-            api_key,
+            credential,
+            api_key_source,
+            protocol_version,
+            policy,
             last_activity: SystemTime::now(),
+        };
+        Self::restore(record, client_lease)
+    }
+
+    /// Rebuilds a session from its record, with fresh runtime state.
+    pub(crate) fn restore(record: SessionRecord, client_lease: ClientLease) -> Self {
+        let events = Arc::new(EventLog::default());
+        let standalone = events.clone();
+        let subscriptions = ResourceSubscriptions::new(Arc::new(move |notification| {
+            standalone.publish(STANDALONE_STREAM, &notification, false)
+        }));
+        Self {
+            session_id: record.session_id,
+            credential: record.credential,
+            api_key_source: record.api_key_source,
+            protocol_version: record.protocol_version,
+            last_activity: record.last_activity,
+            confirmations: Arc::new(ConfirmationStore::default()),
+            policy: Arc::new(record.policy),
+            subscriptions: Arc::new(subscriptions),
+            events,
+            tool_calls: Arc::new(RateWindow::default()),
+            signed: None,
+            client_lease: Arc::new(client_lease),
+        }
+    }
+
+    pub(crate) fn record(&self) -> SessionRecord {
+        SessionRecord {
+            session_id: self.session_id.clone(),
+            credential: self.credential.clone(),
+            api_key_source: self.api_key_source,
+            protocol_version: self.protocol_version,
+            policy: self.policy.as_ref().clone(),
+            last_activity: self.last_activity,
+        }
+    }
+
+    /// How the session appears in logs. Signed tokens carry an encrypted API
+    /// key and work as credentials, so only their inner id is logged.
+    pub(crate) fn log_id(&self) -> &str {
+        match &self.signed {
+            Some(signed) => &signed.id,
+            None => &self.session_id,
         }
     }
 
     pub fn is_expired(&self, timeout: Duration) -> bool {
+        if let Some(signed) = &self.signed
+            && signed.expires_at <= SystemTime::now()
+        {
+            return true;
+        }
         self.last_activity
             .elapsed()
             .map(|elapsed| elapsed > timeout)
@@ -33,8 +141,77 @@ impl SessionState {
     }
 }
 
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// When recent attempts started, for a per-minute limit such as a session's
+/// tool calls.
+#[derive(Debug, Default)]
+pub(crate) struct RateWindow(Mutex<VecDeque<Instant>>);
+
+impl RateWindow {
+    /// Counts an attempt, unless `limit` attempts already started within the
+    /// last minute. Then returns how long until the oldest of them drops out.
+    pub(crate) fn try_acquire(&self, limit: usize) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut calls = self.0.lock().unwrap();
+        while calls
+            .front()
+            .is_some_and(|start| now.duration_since(*start) >= RATE_WINDOW)
+        {
+            calls.pop_front();
+        }
+        if calls.len() >= limit {
+            let oldest = calls.front().copied().unwrap_or(now);
+            return Err(RATE_WINDOW.saturating_sub(now.duration_since(oldest)));
+        }
+        calls.push_back(now);
+        Ok(())
+    }
+}
+
+/// Caps on sessions and tool calls. `None` means unlimited. They are enforced
+/// by this process alone: with signed sessions each replica counts the
+/// sessions it holds and the tool calls it serves, so across N replicas the
+/// effective limits are up to N times higher.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SessionLimits {
+    pub(crate) max_sessions: Option<usize>,
+    pub(crate) max_sessions_per_key: Option<usize>,
+    pub(crate) tool_calls_per_minute: Option<usize>,
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum NewSessionError {
+    #[error("Too many active sessions on this server. Try again later")]
+    TooManySessions,
+    #[error(
+        "Too many active sessions for this API key. End one with DELETE before starting another"
+    )]
+    TooManySessionsForKey,
+    #[error(transparent)]
+    Store(#[from] SessionStoreError),
+}
+
+/// How often revocations by other replicas are picked up.
+const DENY_LIST_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Sessions carried in signed tokens, so that any replica holding the same
+/// secrets can pick them up.
+pub(crate) struct StatelessSessions {
+    pub(crate) tokens: SessionTokens,
+    pub(crate) deny_list: DenyList,
+    pub(crate) router: HevyRouter,
+}
+
 pub struct SessionManager {
     sessions: Arc<DashMap<String, SessionState>>,
+    store: Arc<dyn SessionStore>,
+    stateless: Option<StatelessSessions>,
+    tokens: Option<Arc<TokenStore>>,
+    limits: SessionLimits,
+    /// Held while a session is checked against the limits and inserted.
+    /// Nothing done under it reads or writes files.
+    creating: Mutex<()>,
     timeout: Duration,
 }
 
@@ -42,46 +219,378 @@ impl SessionManager {
     pub fn new(timeout_secs: u64) -> Self {
         Self {
             sessions: Arc::new(DashMap::new()),
+            store: Arc::new(MemorySessionStore),
+            stateless: None,
+            tokens: None,
+            limits: SessionLimits::default(),
+            creating: Mutex::new(()),
             timeout: Duration::from_secs(timeout_secs),
         }
     }
 
-    pub fn new_session(&self, api_key: String) -> SessionState {
-        let session = SessionState::new(api_key);
-        let session_id = session.session_id.clone();
-        self.sessions.insert(session_id.clone(), session.clone());
-        session
+    pub(crate) fn with_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub(crate) fn with_limits(mut self, limits: SessionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub(crate) fn with_stateless(mut self, stateless: StatelessSessions) -> Self {
+        self.stateless = Some(stateless);
+        self
+    }
+
+    /// Looks up the Hevy key of sessions started with an access token in
+    /// `tokens`, and ends those sessions once their token is revoked.
+    pub(crate) fn with_tokens(mut self, tokens: Arc<TokenStore>) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Brings back the sessions in the store that have not expired, and drops
+    /// the ones that have.
+    pub(crate) fn restore(&self, router: &HevyRouter) -> Result<(), SessionStoreError> {
+        let mut expired = Vec::new();
+        for record in self.store.load()? {
+            let Some(api_key) = self.api_key(&record.credential) else {
+                expired.push(record.session_id);
+                continue;
+            };
+            let client_lease = router.lease_client(&api_key);
+            let session = SessionState::restore(record, client_lease);
+            if session.is_expired(self.timeout) {
+                expired.push(session.session_id);
+                continue;
+            }
+            self.sessions.insert(session.session_id.clone(), session);
+        }
+        self.store.remove(&expired)?;
+        if !self.sessions.is_empty() {
+            info!("Restored {} sessions", self.sessions.len());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn new_session(
+        &self,
+        credential: SessionCredential,
+        api_key_source: ApiKeySource,
+        policy: ToolPolicy,
+        protocol_version: ProtocolVersion,
+        client_lease: ClientLease,
+    ) -> Result<SessionState, NewSessionError> {
+        let _creating = self.creating.lock().unwrap();
+        self.check_limits(client_lease.key_id())?;
+        let session = match &self.stateless {
+            Some(stateless) => {
+                let (token, signed) = stateless.tokens.issue(
+                    &credential,
+                    api_key_source,
+                    protocol_version,
+                    &policy,
+                )?;
+                let record = SessionRecord {
+                    session_id: token,
+                    credential,
+                    api_key_source,
+                    protocol_version,
+                    policy,
+                    last_activity: SystemTime::now(),
+                };
+                let mut session = SessionState::restore(record, client_lease);
+                session.signed = Some(signed);
+                session
+            }
+            None => SessionState::new(
+                credential,
+                api_key_source,
+                policy,
+                protocol_version,
+                client_lease,
+            ),
+        };
+        info!(
+            "Session {} uses the API key from the {}",
+            session.log_id(),
+            session.api_key_source.as_str()
+        );
+        self.sessions
+            .insert(session.session_id.clone(), session.clone());
+        if let Err(err) = self.store.save(&session.record()) {
+            warn!("Failed to persist session {}: {}", session.log_id(), err);
+        }
+        Ok(session)
+    }
+
+    fn check_limits(&self, key_id: &str) -> Result<(), NewSessionError> {
+        if self.count_against_limits(key_id).is_ok() {
+            return Ok(());
+        }
+        // Expired sessions count until the next cleanup, so drop them and look
+        // again. Sessions of revoked access tokens are left to the cleanup
+        // task, since finding them reads the token store.
+        let timeout = self.timeout;
+        self.remove_sessions(|session| session.is_expired(timeout));
+        self.count_against_limits(key_id)
+    }
+
+    fn count_against_limits(&self, key_id: &str) -> Result<(), NewSessionError> {
+        if let Some(max) = self.limits.max_sessions
+            && self.sessions.len() >= max
+        {
+            return Err(NewSessionError::TooManySessions);
+        }
+        if let Some(max) = self.limits.max_sessions_per_key
+            && self
+                .sessions
+                .iter()
+                .filter(|session| session.client_lease.key_id() == key_id)
+                .count()
+                >= max
+        {
+            return Err(NewSessionError::TooManySessionsForKey);
+        }
+        Ok(())
+    }
+
+    /// Counts a `tools/call` against the session's per-minute limit. When the
+    /// limit is reached, returns how long until the next call is allowed.
+    pub(crate) fn acquire_tool_call(&self, session: &SessionState) -> Result<(), Duration> {
+        match self.limits.tool_calls_per_minute {
+            Some(limit) => session.tool_calls.try_acquire(limit),
+            None => Ok(()),
+        }
     }
 
     pub fn get_session(&self, session_id: &str) -> Option<SessionState> {
-        self.sessions.get(session_id).map(|entry| entry.clone())
+        let local = self.sessions.get(session_id).map(|entry| entry.clone());
+        let Some(stateless) = &self.stateless else {
+            let session = local?;
+            return self.check_token(session_id, &session).then_some(session);
+        };
+
+        // Sessions issued by another replica are rebuilt from their token.
+        let adopted = local.is_none();
+        let session = match local {
+            Some(session) => session,
+            None => {
+                let (record, signed) = stateless.tokens.verify(session_id)?;
+                let api_key = self.api_key(&record.credential)?;
+                let client_lease = stateless.router.lease_client(&api_key);
+                let mut session = SessionState::restore(record, client_lease);
+                session.signed = Some(signed);
+                session
+            }
+        };
+        let revoked = match &session.signed {
+            Some(signed) => stateless
+                .deny_list
+                .contains(&signed.id)
+                .unwrap_or_else(|err| {
+                    warn!("Failed to read the session deny list: {}", err);
+                    true
+                }),
+            None => true,
+        };
+        if revoked || session.is_expired(self.timeout) {
+            self.sessions.remove(session_id);
+            return None;
+        }
+        if !self.check_token(session_id, &session) {
+            return None;
+        }
+        // A session taken over from another replica counts against this
+        // replica's limits like one it created.
+        let _creating = adopted.then(|| self.creating.lock().unwrap());
+        if adopted
+            && !self.sessions.contains_key(session_id)
+            && let Err(err) = self.check_limits(session.client_lease.key_id())
+        {
+            info!("Not taking over session {}: {}", session.log_id(), err);
+            return None;
+        }
+        Some(
+            self.sessions
+                .entry(session_id.to_string())
+                .or_insert(session)
+                .clone(),
+        )
+    }
+
+    /// The Hevy key behind `credential`, or `None` if its access token was
+    /// revoked or cannot be looked up.
+    fn api_key(&self, credential: &SessionCredential) -> Option<String> {
+        let id = match credential {
+            SessionCredential::ApiKey(api_key) => return Some(api_key.clone()),
+            SessionCredential::Token(id) => id,
+        };
+        let Some(tokens) = &self.tokens else {
+            warn!("A session was started with an access token, but no token store is configured");
+            return None;
+        };
+        tokens.resolve_id(id).unwrap_or_else(|err| {
+            warn!("Access token lookup failed: {}", err);
+            None
+        })
+    }
+
+    /// Whether the access token `session` was started with, if any, is still
+    /// valid. Sessions whose token was revoked are ended here.
+    fn check_token(&self, session_id: &str, session: &SessionState) -> bool {
+        let SessionCredential::Token(id) = &session.credential else {
+            return true;
+        };
+        let valid = match &self.tokens {
+            Some(tokens) => tokens.contains(id),
+            None => Ok(false),
+        };
+        match valid {
+            Ok(true) => true,
+            Ok(false) => {
+                info!(
+                    "Ending session {}: its access token was revoked",
+                    session.log_id()
+                );
+                self.end_sessions(vec![session_id.to_string()]);
+                false
+            }
+            Err(err) => {
+                warn!("Access token lookup failed: {}", err);
+                false
+            }
+        }
+    }
+
+    fn end_sessions(&self, session_ids: Vec<String>) {
+        for session_id in &session_ids {
+            self.sessions.remove(session_id);
+        }
+        if let Err(err) = self.store.remove(&session_ids) {
+            warn!("Failed to remove sessions from the store: {}", err);
+        }
     }
 
     pub fn update_activity(&self, session_id: &str) -> bool {
-        if let Some(mut entry) = self.sessions.get_mut(session_id) {
-            entry.update_activity();
-            true
-        } else {
-            false
+        let Some(mut entry) = self.sessions.get_mut(session_id) else {
+            return false;
+        };
+        entry.update_activity();
+        let last_activity = entry.last_activity;
+        drop(entry);
+        if let Err(err) = self.store.touch(session_id, last_activity) {
+            warn!(
+                "Failed to persist activity for session {}: {}",
+                session_id, err
+            );
         }
+        true
     }
 
     pub fn remove_session(&self, session_id: &str) -> bool {
-        self.sessions.remove(session_id).is_some()
+        if let Some(stateless) = &self.stateless {
+            return self.revoke(stateless, session_id);
+        }
+        let removed = self.sessions.remove(session_id).is_some();
+        if removed && let Err(err) = self.store.remove(&[session_id.to_string()]) {
+            warn!(
+                "Failed to remove session {} from the store: {}",
+                session_id, err
+            );
+        }
+        removed
+    }
+
+    /// Ends a signed session on every replica by adding it to the deny list.
+    fn revoke(&self, stateless: &StatelessSessions, session_id: &str) -> bool {
+        let Some(session) = self.get_session(session_id) else {
+            return false;
+        };
+        self.sessions.remove(session_id);
+        let Some(signed) = &session.signed else {
+            return false;
+        };
+        if let Err(err) = stateless.deny_list.deny(signed) {
+            warn!("Failed to revoke session {}: {}", signed.id, err);
+        }
+        true
     }
 
     pub fn cleanup_expired_sessions(&self) {
         let timeout = self.timeout;
-        self.sessions
-            .retain(|_id, session| !session.is_expired(timeout));
+        // Sessions whose access token was revoked go too, even if nobody uses them again.
+        let active_tokens: Option<HashSet<String>> = self.tokens.as_ref().and_then(|tokens| {
+            tokens
+                .list()
+                .map(|tokens| tokens.into_iter().map(|token| token.id).collect())
+                .inspect_err(|err| warn!("Failed to list access tokens: {}", err))
+                .ok()
+        });
+        self.remove_sessions(|session| {
+            let revoked = match (&session.credential, &active_tokens) {
+                (SessionCredential::Token(token_id), Some(active)) => !active.contains(token_id),
+                _ => false,
+            };
+            revoked || session.is_expired(timeout)
+        });
+        if let Err(err) = self.store.flush() {
+            warn!("Failed to update the session store: {}", err);
+        }
+    }
+
+    /// Drops the sessions `ended` picks. Only touches memory: stores write
+    /// removals out in the background.
+    fn remove_sessions(&self, ended: impl Fn(&SessionState) -> bool) {
+        let mut removed = Vec::new();
+        self.sessions.retain(|id, session| {
+            if ended(session) {
+                removed.push(id.clone());
+                return false;
+            }
+            true
+        });
+        if let Err(err) = self.store.remove(&removed) {
+            warn!("Failed to remove sessions from the store: {}", err);
+        }
     }
 
     pub fn start_cleanup_task(self: Arc<Self>) {
+        if self
+            .stateless
+            .as_ref()
+            .is_some_and(|stateless| stateless.deny_list.is_shared())
+        {
+            let manager = self.clone();
+            spawn(async move {
+                loop {
+                    sleep(DENY_LIST_RELOAD_INTERVAL).await;
+                    let manager = manager.clone();
+                    let result = spawn_blocking(move || manager.reload_deny_list()).await;
+                    if let Ok(Err(err)) = result {
+                        warn!("Failed to read the session deny list: {}", err);
+                    }
+                }
+            });
+        }
         spawn(async move {
             loop {
                 sleep(Duration::from_secs(60)).await;
-                self.cleanup_expired_sessions();
+                // Listing access tokens reads the token store.
+                let manager = self.clone();
+                if let Err(err) = spawn_blocking(move || manager.cleanup_expired_sessions()).await {
+                    warn!("Session cleanup failed: {}", err);
+                }
             }
         });
     }
+
+    /// Picks up sessions revoked by other replicas.
+    pub(crate) fn reload_deny_list(&self) -> Result<(), SessionStoreError> {
+        match &self.stateless {
+            Some(stateless) => stateless.deny_list.reload(),
+            None => Ok(()),
+        }
+    }
 }
@@ -0,0 +1,457 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::crypto::{FileVersion, MasterKey, lock_file, write_private_file};
+use crate::http::session::ApiKeySource;
+use crate::http::store::{SealedCredential, SessionCredential, SessionRecord, SessionStoreError};
+use crate::router::{ProtocolVersion, ToolPolicy};
+
+const MIN_SECRET_BYTES: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    session_id: String,
+    #[serde(flatten)]
+    credential: SealedCredential,
+    api_key_source: ApiKeySource,
+    protocol_version: ProtocolVersion,
+    policy: ToolPolicy,
+    expires_at: u64,
+}
+
+/// What a replica needs to know about a session it did not create.
+#[derive(Clone, Debug)]
+pub(crate) struct SignedSession {
+    pub(crate) id: String,
+    pub(crate) expires_at: SystemTime,
+}
+
+/// Issues and checks self-contained `Mcp-Session-Id` values: the session's
+/// claims, with the API key encrypted under the master key (or just the id of
+/// the session's access token), followed by an
+/// HMAC-SHA256 signature. Any replica with the same secrets can serve them.
+pub(crate) struct SessionTokens {
+    signing_key: Vec<u8>,
+    master_key: MasterKey,
+    lifetime: Duration,
+}
+
+impl SessionTokens {
+    pub(crate) fn new(
+        secret: &str,
+        master_key: &str,
+        lifetime: Duration,
+    ) -> Result<Self, SessionStoreError> {
+        if secret.len() < MIN_SECRET_BYTES {
+            return Err(SessionStoreError::Config(format!(
+                "HEVY_SESSION_SECRET must be at least {} characters",
+                MIN_SECRET_BYTES
+            )));
+        }
+        Ok(Self {
+            signing_key: secret.as_bytes().to_vec(),
+            master_key: MasterKey::parse(master_key).map_err(SessionStoreError::Config)?,
+            lifetime,
+        })
+    }
+
+    /// A token for a new session, which expires a fixed time after it is issued.
+    pub(crate) fn issue(
+        &self,
+        credential: &SessionCredential,
+        api_key_source: ApiKeySource,
+        protocol_version: ProtocolVersion,
+        policy: &ToolPolicy,
+    ) -> Result<(String, SignedSession), SessionStoreError> {
+        let session_id = Uuid::new_v4().to_string();
+        let expires_at = SystemTime::now() + self.lifetime;
+        let claims = Claims {
+            credential: credential.seal(&self.master_key, &session_id)?,
+            session_id: session_id.clone(),
+            api_key_source,
+            protocol_version,
+            policy: policy.clone(),
+            expires_at: unix_secs(expires_at),
+        };
+        let payload = serde_json::to_vec(&claims)
+            .map(|json| URL_SAFE_NO_PAD.encode(json))
+            .map_err(|err| SessionStoreError::Io(err.to_string()))?;
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&payload).finalize().into_bytes());
+        Ok((
+            format!("{}.{}", payload, signature),
+            SignedSession {
+                id: session_id,
+                expires_at,
+            },
+        ))
+    }
+
+    /// The session behind `token`, if the signature holds and it has not expired.
+    pub(crate) fn verify(&self, token: &str) -> Option<(SessionRecord, SignedSession)> {
+        let (payload, signature) = token.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        self.mac(payload).verify_slice(&signature).ok()?;
+
+        let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+        let expires_at = UNIX_EPOCH + Duration::from_secs(claims.expires_at);
+        if expires_at <= SystemTime::now() {
+            return None;
+        }
+        let credential = claims
+            .credential
+            .open(&self.master_key, &claims.session_id)?;
+        Some((
+            SessionRecord {
+                session_id: token.to_string(),
+                credential,
+                api_key_source: claims.api_key_source,
+                protocol_version: claims.protocol_version,
+                policy: claims.policy,
+                last_activity: SystemTime::now(),
+            },
+            SignedSession {
+                id: claims.session_id,
+                expires_at,
+            },
+        ))
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.signing_key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct DenyFile {
+    /// Revoked session ids, with the time their token expires anyway.
+    denied: HashMap<String, u64>,
+}
+
+#[derive(Default)]
+struct Loaded {
+    denied: HashMap<String, u64>,
+    version: Option<FileVersion>,
+    /// Why the last reload failed. Every session counts as revoked until a
+    /// reload succeeds again.
+    error: Option<String>,
+}
+
+/// Sessions revoked before their token expires. Entries are dropped once the
+/// token would have expired, so the list stays small. Replicas that point at
+/// the same file see each other's revocations once they `reload` it.
+pub(crate) struct DenyList {
+    path: Option<PathBuf>,
+    loaded: RwLock<Loaded>,
+}
+
+impl DenyList {
+    pub(crate) fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            loaded: RwLock::new(Loaded::default()),
+        }
+    }
+
+    /// Whether the list lives in a file that other replicas may write to.
+    pub(crate) fn is_shared(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Checks the list as of the last `reload`, without touching the file.
+    pub(crate) fn contains(&self, session_id: &str) -> Result<bool, SessionStoreError> {
+        let loaded = self.loaded.read().unwrap();
+        if let Some(error) = &loaded.error {
+            return Err(SessionStoreError::Io(error.clone()));
+        }
+        Ok(loaded.denied.contains_key(session_id))
+    }
+
+    /// Picks up revocations written by other replicas. Reads the file, so it
+    /// runs at startup and then periodically, away from requests.
+    pub(crate) fn reload(&self) -> Result<(), SessionStoreError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // Readers are only held up while a changed file is read.
+        if let Ok(metadata) = fs::metadata(path) {
+            let loaded = self.loaded.read().unwrap();
+            if loaded.error.is_none() && loaded.version == Some(FileVersion::of(&metadata)) {
+                return Ok(());
+            }
+        }
+        let mut loaded = self.loaded.write().unwrap();
+        let result = self.refresh(&mut loaded);
+        loaded.error = result.as_ref().err().map(ToString::to_string);
+        result
+    }
+
+    pub(crate) fn deny(&self, session: &SignedSession) -> Result<(), SessionStoreError> {
+        let mut loaded = self.loaded.write().unwrap();
+        // Other replicas may write the file too, so it is read again under the
+        // lock rather than trusting the version seen by the last reload.
+        let _lock = match &self.path {
+            Some(path) => Some(
+                lock_file(path)
+                    .map_err(|err| SessionStoreError::Io(format!("{}: {}", path.display(), err)))?,
+            ),
+            None => None,
+        };
+        loaded.version = None;
+        self.refresh(&mut loaded)?;
+        let now = unix_secs(SystemTime::now());
+        loaded.denied.retain(|_, expires_at| *expires_at > now);
+        loaded
+            .denied
+            .insert(session.id.clone(), unix_secs(session.expires_at));
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = serde_json::to_vec_pretty(&DenyFile {
+            denied: loaded.denied.clone(),
+        })
+        .map_err(|err| SessionStoreError::Io(err.to_string()))?;
+        write_private_file(path, &contents)
+            .map_err(|err| SessionStoreError::Io(format!("{}: {}", path.display(), err)))?;
+        loaded.version = fs::metadata(path)
+            .map(|metadata| FileVersion::of(&metadata))
+            .ok();
+        Ok(())
+    }
+
+    fn refresh(&self, loaded: &mut Loaded) -> Result<(), SessionStoreError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let io_error =
+            |err: std::io::Error| SessionStoreError::Io(format!("{}: {}", path.display(), err));
+        let version = match fs::metadata(path) {
+            Ok(metadata) => FileVersion::of(&metadata),
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(io_error(err)),
+        };
+        if loaded.version == Some(version) {
+            return Ok(());
+        }
+        let contents = fs::read(path).map_err(io_error)?;
+        let file: DenyFile = serde_json::from_slice(&contents).map_err(|err| {
+            SessionStoreError::Io(format!(
+                "{} is not a valid deny list: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        *loaded = Loaded {
+            denied: file.denied,
+            version: Some(version),
+            error: None,
+        };
+        Ok(())
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    use super::*;
+    use crate::crypto::generate_master_key;
+    use crate::tokens::random_token;
+
+    const SECRET: &str = "a-session-secret-of-at-least-32-characters";
+
+    fn session_tokens(master_key: &str, lifetime: Duration) -> SessionTokens {
+        SessionTokens::new(SECRET, master_key, lifetime).unwrap()
+    }
+
+    fn issue(tokens: &SessionTokens, credential: &SessionCredential) -> (String, SignedSession) {
+        tokens
+            .issue(
+                credential,
+                ApiKeySource::Header,
+                ProtocolVersion::LATEST,
+                &ToolPolicy::default(),
+            )
+            .unwrap()
+    }
+
+    fn api_key() -> SessionCredential {
+        SessionCredential::ApiKey("hevy-api-key".to_string())
+    }
+
+    #[test]
+    fn short_secret_is_refused() {
+        let result =
+            SessionTokens::new("too-short", &generate_master_key(), Duration::from_secs(60));
+        assert!(matches!(result, Err(SessionStoreError::Config(_))));
+    }
+
+    #[test]
+    fn issued_token_verifies_on_another_replica() {
+        let master_key = generate_master_key();
+        let (token, signed) = issue(
+            &session_tokens(&master_key, Duration::from_secs(60)),
+            &api_key(),
+        );
+
+        let replica = session_tokens(&master_key, Duration::from_secs(60));
+        let (record, verified) = replica.verify(&token).unwrap();
+        assert_eq!(record.session_id, token);
+        assert_eq!(record.credential, api_key());
+        assert_eq!(record.api_key_source, ApiKeySource::Header);
+        assert_eq!(verified.id, signed.id);
+    }
+
+    #[test]
+    fn token_carries_the_api_key_only_encrypted() {
+        let tokens = session_tokens(&generate_master_key(), Duration::from_secs(60));
+        let (token, _) = issue(&tokens, &api_key());
+        let (payload, _) = token.split_once('.').unwrap();
+        let claims = String::from_utf8(URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+        assert!(!claims.contains("hevy-api-key"));
+    }
+
+    #[test]
+    fn access_token_sessions_carry_only_the_token_id() {
+        let tokens = session_tokens(&generate_master_key(), Duration::from_secs(60));
+        let credential = SessionCredential::Token("token-id".to_string());
+        let (token, _) = issue(&tokens, &credential);
+        assert_eq!(tokens.verify(&token).unwrap().0.credential, credential);
+    }
+
+    #[test]
+    fn tampered_token_is_rejected() {
+        let tokens = session_tokens(&generate_master_key(), Duration::from_secs(60));
+        let (token, _) = issue(&tokens, &api_key());
+        let (payload, signature) = token.split_once('.').unwrap();
+
+        let mut claims: serde_json::Value =
+            serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+        claims["expires_at"] = serde_json::json!(u64::MAX);
+        let forged = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        assert!(
+            tokens
+                .verify(&format!("{}.{}", forged, signature))
+                .is_none()
+        );
+
+        let mut bad_signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        bad_signature[0] ^= 1;
+        let bad_signature = URL_SAFE_NO_PAD.encode(bad_signature);
+        assert!(
+            tokens
+                .verify(&format!("{}.{}", payload, bad_signature))
+                .is_none()
+        );
+        assert!(tokens.verify(payload).is_none());
+    }
+
+    #[test]
+    fn token_signed_with_another_secret_is_rejected() {
+        let master_key = generate_master_key();
+        let (token, _) = issue(
+            &session_tokens(&master_key, Duration::from_secs(60)),
+            &api_key(),
+        );
+        let other =
+            SessionTokens::new(&SECRET.repeat(2), &master_key, Duration::from_secs(60)).unwrap();
+        assert!(other.verify(&token).is_none());
+    }
+
+    #[test]
+    fn expired_token_is_rejected() {
+        let tokens = session_tokens(&generate_master_key(), Duration::ZERO);
+        let (token, _) = issue(&tokens, &api_key());
+        assert!(tokens.verify(&token).is_none());
+    }
+
+    #[test]
+    fn denied_session_is_on_the_deny_list() {
+        let tokens = session_tokens(&generate_master_key(), Duration::from_secs(60));
+        let (_, signed) = issue(&tokens, &api_key());
+        let (_, other) = issue(&tokens, &api_key());
+        let deny_list = DenyList::new(None);
+        deny_list.deny(&signed).unwrap();
+        assert!(deny_list.contains(&signed.id).unwrap());
+        assert!(!deny_list.contains(&other.id).unwrap());
+    }
+
+    #[test]
+    fn replicas_sharing_a_deny_file_keep_every_revocation() {
+        let path = env::temp_dir().join(format!("hevy-deny-{}.json", random_token("")));
+        let tokens = session_tokens(&generate_master_key(), Duration::from_secs(60));
+        let sessions: Vec<SignedSession> = (0..64).map(|_| issue(&tokens, &api_key()).1).collect();
+
+        let replicas: Vec<Arc<DenyList>> = (0..8)
+            .map(|_| Arc::new(DenyList::new(Some(path.clone()))))
+            .collect();
+        let start = Arc::new(Barrier::new(sessions.len()));
+        let handles: Vec<_> = sessions
+            .iter()
+            .enumerate()
+            .map(|(index, session)| {
+                let replica = replicas[index % replicas.len()].clone();
+                let session = session.clone();
+                let start = start.clone();
+                thread::spawn(move || {
+                    start.wait();
+                    replica.deny(&session).unwrap()
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let reader = DenyList::new(Some(path.clone()));
+        reader.reload().unwrap();
+        for session in &sessions {
+            assert!(reader.contains(&session.id).unwrap());
+        }
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("lock"));
+    }
+
+    #[test]
+    fn revocations_from_other_replicas_show_up_after_a_reload() {
+        let path = env::temp_dir().join(format!("hevy-deny-{}.json", random_token("")));
+        let tokens = session_tokens(&generate_master_key(), Duration::from_secs(60));
+        let (_, signed) = issue(&tokens, &api_key());
+        let replica = DenyList::new(Some(path.clone()));
+        let other = DenyList::new(Some(path.clone()));
+        replica.reload().unwrap();
+
+        other.deny(&signed).unwrap();
+        assert!(!replica.contains(&signed.id).unwrap());
+        replica.reload().unwrap();
+        assert!(replica.contains(&signed.id).unwrap());
+
+        fs::write(&path, "not json").unwrap();
+        assert!(replica.reload().is_err());
+        assert!(replica.contains(&signed.id).is_err());
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("lock"));
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::runtime::Handle;
+use tracing::warn;
+
+use crate::crypto::{MasterKey, Sealed, write_private_file};
+use crate::http::session::ApiKeySource;
+use crate::router::{ProtocolVersion, ToolPolicy};
+
+/// The part of a session that outlives the process. Event logs, subscriptions
+/// and pending confirmations start over when a session is restored.
+#[derive(Clone, Debug)]
+pub(crate) struct SessionRecord {
+    pub(crate) session_id: String,
+    pub(crate) credential: SessionCredential,
+    pub(crate) api_key_source: ApiKeySource,
+    pub(crate) protocol_version: ProtocolVersion,
+    pub(crate) policy: ToolPolicy,
+    pub(crate) last_activity: SystemTime,
+}
+
+/// What a session calls Hevy with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SessionCredential {
+    /// A Hevy API key the client sent, or the server's default key.
+    ApiKey(String),
+    /// The id of the server-issued access token the session was started with.
+    /// Its Hevy key stays in the token store, which is checked on every request.
+    Token(String),
+}
+
+impl SessionCredential {
+    /// Encrypts an API key under `master_key`; token ids are not secret.
+    pub(crate) fn seal(
+        &self,
+        master_key: &MasterKey,
+        record_id: &str,
+    ) -> Result<SealedCredential, SessionStoreError> {
+        Ok(match self {
+            SessionCredential::ApiKey(api_key) => SealedCredential {
+                api_key: Some(
+                    master_key
+                        .seal(api_key, record_id)
+                        .map_err(SessionStoreError::Io)?,
+                ),
+                token_id: None,
+            },
+            SessionCredential::Token(id) => SealedCredential {
+                api_key: None,
+                token_id: Some(id.clone()),
+            },
+        })
+    }
+}
+
+/// A `SessionCredential` as it is written to the store or a session token.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SealedCredential {
+    #[serde(flatten)]
+    api_key: Option<Sealed>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token_id: Option<String>,
+}
+
+impl SealedCredential {
+    /// `None` if the API key cannot be decrypted with `master_key`.
+    pub(crate) fn open(
+        &self,
+        master_key: &MasterKey,
+        record_id: &str,
+    ) -> Option<SessionCredential> {
+        match (&self.token_id, &self.api_key) {
+            (Some(id), _) => Some(SessionCredential::Token(id.clone())),
+            (None, Some(api_key)) => master_key
+                .open(api_key, record_id)
+                .map(SessionCredential::ApiKey),
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum SessionStoreError {
+    #[error("{0}")]
+    Config(String),
+    #[error("session store error: {0}")]
+    Io(String),
+}
+
+/// Where `SessionManager` persists sessions. The manager keeps live sessions
+/// in memory either way; a store only has to hand them back after a restart.
+pub(crate) trait SessionStore: Send + Sync {
+    fn load(&self) -> Result<Vec<SessionRecord>, SessionStoreError>;
+
+    fn save(&self, record: &SessionRecord) -> Result<(), SessionStoreError>;
+
+    /// Records activity. Called on every request, so stores may buffer it
+    /// until the next `flush`.
+    fn touch(&self, session_id: &str, last_activity: SystemTime) -> Result<(), SessionStoreError>;
+
+    fn remove(&self, session_ids: &[String]) -> Result<(), SessionStoreError>;
+
+    fn flush(&self) -> Result<(), SessionStoreError> {
+        Ok(())
+    }
+}
+
+/// Keeps nothing beyond the manager's own map, so sessions end with the process.
+pub(crate) struct MemorySessionStore;
+
+impl SessionStore for MemorySessionStore {
+    fn load(&self) -> Result<Vec<SessionRecord>, SessionStoreError> {
+        Ok(Vec::new())
+    }
+
+    fn save(&self, _record: &SessionRecord) -> Result<(), SessionStoreError> {
+        Ok(())
+    }
+
+    fn touch(
+        &self,
+        _session_id: &str,
+        _last_activity: SystemTime,
+    ) -> Result<(), SessionStoreError> {
+        Ok(())
+    }
+
+    fn remove(&self, _session_ids: &[String]) -> Result<(), SessionStoreError> {
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSession {
+    session_id: String,
+    #[serde(flatten)]
+    credential: SealedCredential,
+    api_key_source: ApiKeySource,
+    protocol_version: ProtocolVersion,
+    policy: ToolPolicy,
+    last_activity: SystemTime,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StoreFile {
+    sessions: Vec<StoredSession>,
+}
+
+/// Sessions in a JSON file with every API key encrypted under the master key.
+/// Sessions started with an access token store only the token's id. New and
+/// removed sessions are written straight away, on a blocking thread, and
+/// changes made while a write is running are written together after it;
+/// activity is written when the cleanup task flushes the store.
+pub(crate) struct FileSessionStore {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    path: PathBuf,
+    master_key: MasterKey,
+    records: Mutex<HashMap<String, SessionRecord>>,
+    dirty: AtomicBool,
+    /// Set while a write is queued or running.
+    writing: AtomicBool,
+}
+
+impl FileSessionStore {
+    pub(crate) fn open(
+        path: impl Into<PathBuf>,
+        master_key: &str,
+    ) -> Result<Self, SessionStoreError> {
+        Ok(Self {
+            shared: Arc::new(Shared {
+                path: path.into(),
+                master_key: MasterKey::parse(master_key).map_err(SessionStoreError::Config)?,
+                records: Mutex::new(HashMap::new()),
+                dirty: AtomicBool::new(false),
+                writing: AtomicBool::new(false),
+            }),
+        })
+    }
+
+    /// Writes the records out on a blocking thread, unless a write is already
+    /// queued, which will pick up the latest records when it runs.
+    fn schedule_write(&self) {
+        self.shared.dirty.store(true, Ordering::Release);
+        if self.shared.writing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let shared = self.shared.clone();
+        match Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(move || shared.write_pending());
+            }
+            Err(_) => shared.write_pending(),
+        }
+    }
+}
+
+impl Shared {
+    fn write_pending(&self) {
+        loop {
+            while self.dirty.swap(false, Ordering::AcqRel) {
+                let records: Vec<SessionRecord> =
+                    self.records.lock().unwrap().values().cloned().collect();
+                if let Err(err) = self.write(&records) {
+                    warn!("Failed to update the session store: {}", err);
+                }
+            }
+            self.writing.store(false, Ordering::Release);
+            // A change may have come in after the last write but before
+            // `writing` was cleared, with nobody left to write it.
+            if !self.dirty.load(Ordering::Acquire) || self.writing.swap(true, Ordering::AcqRel) {
+                return;
+            }
+        }
+    }
+
+    fn write(&self, records: &[SessionRecord]) -> Result<(), SessionStoreError> {
+        let sessions = records
+            .iter()
+            .map(|record| {
+                Ok(StoredSession {
+                    session_id: record.session_id.clone(),
+                    credential: record
+                        .credential
+                        .seal(&self.master_key, &record.session_id)?,
+                    api_key_source: record.api_key_source,
+                    protocol_version: record.protocol_version,
+                    policy: record.policy.clone(),
+                    last_activity: record.last_activity,
+                })
+            })
+            .collect::<Result<_, SessionStoreError>>()?;
+        let contents = serde_json::to_vec_pretty(&StoreFile { sessions })
+            .map_err(|err| SessionStoreError::Io(err.to_string()))?;
+        write_private_file(&self.path, &contents)
+            .map_err(|err| SessionStoreError::Io(format!("{}: {}", self.path.display(), err)))
+    }
+}
+
+impl SessionStore for FileSessionStore {
+    fn load(&self) -> Result<Vec<SessionRecord>, SessionStoreError> {
+        let shared = &self.shared;
+        let contents = match fs::read(&shared.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(SessionStoreError::Io(format!(
+                    "{}: {}",
+                    shared.path.display(),
+                    err
+                )));
+            }
+        };
+        let file: StoreFile = serde_json::from_slice(&contents).map_err(|err| {
+            SessionStoreError::Io(format!(
+                "{} is not a valid session store: {}",
+                shared.path.display(),
+                err
+            ))
+        })?;
+
+        let mut records = shared.records.lock().unwrap();
+        records.clear();
+        for stored in file.sessions {
+            let Some(credential) = stored
+                .credential
+                .open(&shared.master_key, &stored.session_id)
+            else {
+                warn!(
+                    "Dropping stored session {}: its API key cannot be decrypted with HEVY_MASTER_KEY",
+                    stored.session_id
+                );
+                continue;
+            };
+            records.insert(
+                stored.session_id.clone(),
+                SessionRecord {
+                    session_id: stored.session_id,
+                    credential,
+                    api_key_source: stored.api_key_source,
+                    protocol_version: stored.protocol_version,
+                    policy: stored.policy,
+                    last_activity: stored.last_activity,
+                },
+            );
+        }
+        Ok(records.values().cloned().collect())
+    }
+
+    fn save(&self, record: &SessionRecord) -> Result<(), SessionStoreError> {
+        self.shared
+            .records
+            .lock()
+            .unwrap()
+            .insert(record.session_id.clone(), record.clone());
+        self.schedule_write();
+        Ok(())
+    }
+
+    fn touch(&self, session_id: &str, last_activity: SystemTime) -> Result<(), SessionStoreError> {
+        if let Some(record) = self.shared.records.lock().unwrap().get_mut(session_id) {
+            record.last_activity = last_activity;
+            self.shared.dirty.store(true, Ordering::Release);
+        }
+        Ok(())
+    }
+
+    fn remove(&self, session_ids: &[String]) -> Result<(), SessionStoreError> {
+        let mut records = self.shared.records.lock().unwrap();
+        let before = records.len();
+        for session_id in session_ids {
+            records.remove(session_id);
+        }
+        if records.len() != before {
+            drop(records);
+            self.schedule_write();
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), SessionStoreError> {
+        if self.shared.dirty.load(Ordering::Acquire) {
+            self.schedule_write();
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
+mod crypto;
 pub mod hevy;
 pub mod http;
 pub mod router;
+pub mod stdio;
+pub mod tokens;
 
 pub use hevy::HevyClient;
 pub use router::HevyRouter;
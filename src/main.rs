@@ -1,12 +1,14 @@
 use std::env;
-use std::io::stderr;
+use std::io::{stderr, stdin};
 use std::net::AddrParseError;
+use std::time::Duration;
 
 use thiserror::Error;
 use tracing::{Level, info};
 use tracing_subscriber::FmtSubscriber;
 
-use hevy_mcp_server::{HevyRouter, http};
+use hevy_mcp_server::tokens::{TokenStore, generate_master_key};
+use hevy_mcp_server::{HevyRouter, http, stdio};
 
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
@@ -16,6 +18,47 @@ async fn main() -> Result<(), AppError> {
         .with_writer(stderr)
         .init();
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("master-key") if args.len() == 1 => {
+            println!("{}", generate_master_key());
+            return Ok(());
+        }
+        Some("tokens") => return tokens_command(&args[1..]),
+        _ => {}
+    }
+
+    let transport = match env::var("HEVY_TRANSPORT") {
+        Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "" | "http" => Transport::Http,
+            "stdio" => Transport::Stdio,
+            _ => {
+                return Err(AppError::EnvVar(format!(
+                    "HEVY_TRANSPORT must be http or stdio, got {}",
+                    value
+                )));
+            }
+        },
+        Err(env::VarError::NotPresent) => Transport::Http,
+        Err(env::VarError::NotUnicode(_)) => {
+            return Err(AppError::EnvVar(
+                "HEVY_TRANSPORT must be valid UTF-8".to_string(),
+            ));
+        }
+    };
+    let mut stdio_flag = false;
+    for arg in args {
+        match arg.as_str() {
+            "--stdio" => stdio_flag = true,
+            _ => return Err(AppError::InvalidArg(arg)),
+        }
+    }
+    let transport = if stdio_flag {
+        Transport::Stdio
+    } else {
+        transport
+    };
+
     let api_key = match env::var("HEVY_API_KEY") {
         Ok(value) => Some(value),
         Err(env::VarError::NotPresent) => None,
@@ -26,12 +69,131 @@ async fn main() -> Result<(), AppError> {
         }
     };
 
-    match &api_key {
-        Some(_) => info!("Running in single-user mode with default API key"),
-        None => info!("Running in multi-user mode - API key required per request"),
+    match (&api_key, transport) {
+        (None, Transport::Stdio) => {
+            return Err(AppError::EnvVar(
+                "HEVY_API_KEY is required in stdio mode".to_string(),
+            ));
+        }
+        (Some(_), _) => info!("Running in single-user mode with default API key"),
+        (None, Transport::Http) => {
+            info!("Running in multi-user mode - API key required per request")
+        }
+    }
+
+    let confirmation_tools = match env::var("HEVY_CONFIRM_TOOLS") {
+        Ok(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Err(env::VarError::NotPresent) => Vec::new(),
+        Err(env::VarError::NotUnicode(_)) => {
+            return Err(AppError::EnvVar(
+                "HEVY_CONFIRM_TOOLS must be valid UTF-8".to_string(),
+            ));
+        }
+    };
+    if !confirmation_tools.is_empty() {
+        info!(
+            "Two-phase confirmation enabled for: {}",
+            confirmation_tools.join(", ")
+        );
+    }
+
+    let read_only = match env::var("HEVY_READ_ONLY") {
+        Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" => true,
+            "" | "0" | "false" | "no" => false,
+            _ => {
+                return Err(AppError::EnvVar(format!(
+                    "HEVY_READ_ONLY must be true or false, got {}",
+                    value
+                )));
+            }
+        },
+        Err(env::VarError::NotPresent) => false,
+        Err(env::VarError::NotUnicode(_)) => {
+            return Err(AppError::EnvVar(
+                "HEVY_READ_ONLY must be valid UTF-8".to_string(),
+            ));
+        }
+    };
+    if read_only {
+        info!("Running in read-only mode - write tools are disabled");
+    }
+
+    let poll_interval_secs = match env::var("HEVY_POLL_INTERVAL_SECS") {
+        Ok(value) => value
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .ok_or_else(|| {
+                AppError::EnvVar(format!(
+                    "HEVY_POLL_INTERVAL_SECS must be a positive number of seconds, got {}",
+                    value
+                ))
+            })?,
+        Err(env::VarError::NotPresent) => 60,
+        Err(env::VarError::NotUnicode(_)) => {
+            return Err(AppError::EnvVar(
+                "HEVY_POLL_INTERVAL_SECS must be valid UTF-8".to_string(),
+            ));
+        }
+    };
+
+    let max_clients = match env::var("HEVY_CLIENT_CACHE_SIZE") {
+        Ok(value) => value
+            .parse::<usize>()
+            .ok()
+            .filter(|size| *size > 0)
+            .ok_or_else(|| {
+                AppError::EnvVar(format!(
+                    "HEVY_CLIENT_CACHE_SIZE must be a positive number, got {}",
+                    value
+                ))
+            })?,
+        Err(env::VarError::NotPresent) => 256,
+        Err(env::VarError::NotUnicode(_)) => {
+            return Err(AppError::EnvVar(
+                "HEVY_CLIENT_CACHE_SIZE must be valid UTF-8".to_string(),
+            ));
+        }
+    };
+
+    let client_idle_timeout_secs = match env::var("HEVY_CLIENT_IDLE_TIMEOUT_SECS") {
+        Ok(value) => value
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .ok_or_else(|| {
+                AppError::EnvVar(format!(
+                    "HEVY_CLIENT_IDLE_TIMEOUT_SECS must be a positive number of seconds, got {}",
+                    value
+                ))
+            })?,
+        Err(env::VarError::NotPresent) => 900,
+        Err(env::VarError::NotUnicode(_)) => {
+            return Err(AppError::EnvVar(
+                "HEVY_CLIENT_IDLE_TIMEOUT_SECS must be valid UTF-8".to_string(),
+            ));
+        }
+    };
+
+    let router = HevyRouter::new(api_key)
+        .with_read_only(read_only)
+        .with_poll_interval(Duration::from_secs(poll_interval_secs))
+        .with_client_cache(max_clients, Duration::from_secs(client_idle_timeout_secs))
+        .with_confirmation_tools(confirmation_tools)
+        .map_err(|err| AppError::EnvVar(format!("HEVY_CONFIRM_TOOLS: {}", err)))?;
+
+    if transport == Transport::Stdio {
+        return stdio::serve(router)
+            .await
+            .map_err(|err| AppError::StdioServe(err.to_string()));
     }
 
-    let router = HevyRouter::new(api_key);
     let addr = match env::var("HEVY_HTTP_ADDR") {
         Ok(value) => value,
         Err(env::VarError::NotPresent) => "127.0.0.1:3000".to_string(),
@@ -49,12 +211,79 @@ async fn main() -> Result<(), AppError> {
     Ok(())
 }
 
+/// `tokens issue [--label <label>]`, `tokens list` and `tokens revoke <id>`,
+/// against the store configured by `HEVY_TOKEN_STORE` and `HEVY_MASTER_KEY`.
+fn tokens_command(args: &[String]) -> Result<(), AppError> {
+    let store = TokenStore::from_env()
+        .map_err(|err| AppError::Tokens(err.to_string()))?
+        .ok_or_else(|| {
+            AppError::EnvVar("HEVY_TOKEN_STORE is required to manage access tokens".to_string())
+        })?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args[..] {
+        ["issue"] | ["issue", "--label", _] => {
+            let label = args.get(2).map(|label| label.to_string());
+            eprintln!("Enter the Hevy API key to issue a token for:");
+            let mut api_key = String::new();
+            stdin()
+                .read_line(&mut api_key)
+                .map_err(|err| AppError::Tokens(err.to_string()))?;
+            let api_key = api_key.trim();
+            if api_key.is_empty() {
+                return Err(AppError::Tokens("no Hevy API key given".to_string()));
+            }
+            let (token, info) = store
+                .issue(api_key, label)
+                .map_err(|err| AppError::Tokens(err.to_string()))?;
+            eprintln!("Issued token {}. It will not be shown again.", info.id);
+            println!("{}", token);
+        }
+        ["list"] => {
+            let tokens = store
+                .list()
+                .map_err(|err| AppError::Tokens(err.to_string()))?;
+            for info in tokens {
+                println!(
+                    "{}  {}  {}",
+                    info.id,
+                    info.created_at.to_rfc3339(),
+                    info.label.unwrap_or_default()
+                );
+            }
+        }
+        ["revoke", id] => {
+            let info = store
+                .revoke(id)
+                .map_err(|err| AppError::Tokens(err.to_string()))?;
+            eprintln!("Revoked token {}", info.id);
+        }
+        _ => return Err(AppError::InvalidArg(format!("tokens {}", args.join(" ")))),
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Transport {
+    Http,
+    Stdio,
+}
+
 #[derive(Error, Debug)]
 enum AppError {
     #[error("{0}")]
     EnvVar(String),
+    #[error(
+        "Unknown argument: {0} (expected --stdio, master-key, tokens issue [--label <label>], \
+        tokens list or tokens revoke <id>)"
+    )]
+    InvalidArg(String),
     #[error("HEVY_HTTP_ADDR must be a valid socket address: {0}")]
     InvalidAddr(AddrParseError),
     #[error("HTTP server error: {0}")]
     HttpServe(String),
+    #[error("Stdio server error: {0}")]
+    StdioServe(String),
+    #[error("Token error: {0}")]
+    Tokens(String),
 }
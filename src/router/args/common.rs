@@ -1,20 +1,96 @@
-use mcp_spec::handler::ToolError;
+use mcp_spec::handler::ToolError::{self, InvalidParameters};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 
 pub(crate) fn parse_args<T: DeserializeOwned>(arguments: Value) -> Result<T, ToolError> {
     serde_path_to_error::deserialize(arguments)
-        .map_err(|err| ToolError::InvalidParameters(format!("invalid parameters: {}", err)))
+        .map_err(|err| InvalidParameters(format!("invalid parameters: {}", err)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct PaginationArgs {
+    /// Page number (1-indexed)
     pub page: Option<u32>,
+    /// Number of items per page (max 10, or 100 for exercise templates)
     pub page_size: Option<u32>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct IdArgs {
+    /// The ID to look up
     pub id: String,
 }
+
+#[derive(Deserialize, JsonSchema)]
+pub(crate) struct DryRunArgs {
+    /// Validate and return the request body without sending it to Hevy
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+pub(crate) fn existing_position(index: usize, len: usize, field: &str) -> Result<usize, ToolError> {
+    if index == 0 || index > len {
+        return Err(InvalidParameters(format!(
+            "{} {} is out of range (1..={})",
+            field, index, len
+        )));
+    }
+    Ok(index - 1)
+}
+
+pub(crate) fn insert_position(
+    position: Option<usize>,
+    len: usize,
+    what: &str,
+) -> Result<usize, ToolError> {
+    match position {
+        None => Ok(len),
+        Some(position) if position >= 1 && position <= len + 1 => Ok(position - 1),
+        Some(position) => Err(InvalidParameters(format!(
+            "{} position {} is out of range (1..={})",
+            what,
+            position,
+            len + 1
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_positions_are_one_indexed() {
+        assert_eq!(existing_position(1, 3, "set_index").unwrap(), 0);
+        assert_eq!(existing_position(3, 3, "set_index").unwrap(), 2);
+        for index in [0, 4] {
+            let Err(InvalidParameters(message)) = existing_position(index, 3, "set_index") else {
+                panic!("{} should be out of range", index);
+            };
+            assert_eq!(
+                message,
+                format!("set_index {} is out of range (1..=3)", index)
+            );
+        }
+        assert!(existing_position(1, 0, "set_index").is_err());
+    }
+
+    #[test]
+    fn insert_positions_may_follow_the_last_item() {
+        assert_eq!(insert_position(None, 3, "set").unwrap(), 3);
+        assert_eq!(insert_position(Some(1), 3, "set").unwrap(), 0);
+        assert_eq!(insert_position(Some(4), 3, "set").unwrap(), 3);
+        assert_eq!(insert_position(Some(1), 0, "set").unwrap(), 0);
+        for position in [0, 5] {
+            let Err(InvalidParameters(message)) = insert_position(Some(position), 3, "set") else {
+                panic!("{} should be out of range", position);
+            };
+            assert_eq!(
+                message,
+                format!("set position {} is out of range (1..=4)", position)
+            );
+        }
+    }
+}
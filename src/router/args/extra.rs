@@ -1,26 +1,38 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::hevy::{Equipment, ExerciseType, MuscleGroup};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct WorkoutEventsArgs {
+    /// Page number (1-indexed)
     pub page: Option<u32>,
+    /// Number of items per page (max 10)
     pub page_size: Option<u32>,
+    /// ISO 8601 timestamp to start from
     pub since: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct ExerciseHistoryArgs {
+    /// Exercise template ID
     pub exercise_template_id: String,
+    /// Optional ISO 8601 start date
     pub start_date: Option<String>,
+    /// Optional ISO 8601 end date
     pub end_date: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct CreateExerciseTemplateArgs {
+    /// Exercise title
     pub title: String,
+    /// Exercise type
     pub exercise_type: ExerciseType,
+    /// Equipment category
     pub equipment_category: Equipment,
+    /// Primary muscle group
     pub muscle_group: MuscleGroup,
+    /// Optional additional muscle groups
     pub other_muscles: Option<Vec<MuscleGroup>>,
 }
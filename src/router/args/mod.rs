@@ -1,11 +1,17 @@
 mod common;
 mod extra;
 mod routine;
+mod routine_edit;
 mod routine_folder;
 mod workout;
+mod workout_patch;
 
-pub(crate) use common::{IdArgs, PaginationArgs, parse_args};
+pub(crate) use common::{DryRunArgs, IdArgs, PaginationArgs, parse_args};
 pub(crate) use extra::{CreateExerciseTemplateArgs, ExerciseHistoryArgs, WorkoutEventsArgs};
 pub(crate) use routine::{CreateRoutineArgs, UpdateRoutineArgs};
+pub(crate) use routine_edit::EditRoutineArgs;
 pub(crate) use routine_folder::CreateRoutineFolderArgs;
-pub(crate) use workout::{CreateWorkoutArgs, UpdateWorkoutArgs};
+pub(crate) use workout::{
+    CreateWorkoutArgs, LogWorkoutFromRoutineArgs, SetOverrideArgs, UpdateWorkoutArgs,
+};
+pub(crate) use workout_patch::PatchWorkoutArgs;
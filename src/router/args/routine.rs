@@ -1,16 +1,21 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_with::serde_as;
 
 use crate::hevy::{RepRange, RoutineExerciseInput, RoutineInput, RoutineSetInput, SetType};
 
 #[serde_as]
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct RoutineArgsCommon {
+    /// Routine title
     pub title: String,
+    /// Folder ID to place the routine in
     pub folder_id: Option<String>,
+    /// Routine notes
     pub notes: Option<String>,
+    /// Exercises in the routine
     #[serde(default)]
-    #[serde_as(as = "serde_with::DefaultOnNull")]
+    #[serde_as(deserialize_as = "serde_with::DefaultOnNull")]
     pub exercises: Vec<RoutineExerciseArg>,
 }
 
@@ -25,19 +30,24 @@ impl RoutineArgsCommon {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct CreateRoutineArgs {
     #[serde(flatten)]
     pub routine: RoutineArgsCommon,
 }
 
 #[serde_as]
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct RoutineUpdateArgsCommon {
+    /// Routine title
     pub title: String,
+    /// Folder ID to move the routine to
+    pub folder_id: Option<String>,
+    /// Routine notes
     pub notes: Option<String>,
+    /// Exercises in the routine
     #[serde(default)]
-    #[serde_as(as = "serde_with::DefaultOnNull")]
+    #[serde_as(deserialize_as = "serde_with::DefaultOnNull")]
     pub exercises: Vec<RoutineExerciseArg>,
 }
 
@@ -45,46 +55,61 @@ impl RoutineUpdateArgsCommon {
     pub(crate) fn into_input(self) -> RoutineInput {
         RoutineInput {
             title: self.title,
-            folder_id: None,
+            folder_id: self.folder_id,
             notes: self.notes,
             exercises: self.exercises.into_iter().map(Into::into).collect(),
         }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct UpdateRoutineArgs {
+    /// The routine ID to update
     pub id: String,
     #[serde(flatten)]
     pub routine: RoutineUpdateArgsCommon,
 }
 
 #[serde_as]
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct RoutineExerciseArg {
+    /// Exercise template ID
     pub exercise_template_id: String,
+    /// Superset group ID
     pub superset_id: Option<u32>,
+    /// Rest time in seconds between sets
     pub rest_seconds: Option<u32>,
+    /// Exercise notes
     pub notes: Option<String>,
+    /// Sets in the exercise
     #[serde(default)]
-    #[serde_as(as = "serde_with::DefaultOnNull")]
+    #[serde_as(deserialize_as = "serde_with::DefaultOnNull")]
     pub sets: Vec<RoutineSetInputArgs>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct RoutineSetInputArgs {
+    /// Type of set
     pub set_type: Option<SetType>,
+    /// Weight in kg
     pub weight_kg: Option<f64>,
+    /// Target number of reps
     pub reps: Option<u32>,
+    /// Target rep range
     pub rep_range: Option<RepRangeArgs>,
+    /// Duration in seconds
     pub duration_seconds: Option<u32>,
+    /// Distance in meters
     pub distance_meters: Option<f64>,
+    /// Custom metric value
     pub custom_metric: Option<f64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct RepRangeArgs {
+    /// Lowest number of reps
     pub start: u32,
+    /// Highest number of reps
     pub end: u32,
 }
 
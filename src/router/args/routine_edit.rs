@@ -0,0 +1,254 @@
+use std::collections::BTreeSet;
+
+use mcp_spec::handler::ToolError::{self, InvalidParameters};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_with::serde_as;
+
+use crate::hevy::{RoutineExerciseInput, RoutineInput, RoutineSetInput};
+use crate::router::args::common::{existing_position, insert_position};
+use crate::router::args::routine::{RoutineExerciseArg, RoutineSetInputArgs};
+
+#[serde_as]
+#[derive(Deserialize, JsonSchema)]
+pub(crate) struct EditRoutineArgs {
+    /// The routine ID to edit
+    pub id: String,
+    /// Operations applied in order
+    #[serde(default)]
+    #[serde_as(deserialize_as = "serde_with::DefaultOnNull")]
+    pub operations: Vec<RoutineEditOp>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum RoutineEditOp {
+    /// Replace the routine title
+    SetTitle { title: String },
+    /// Replace the notes, omit them to clear
+    SetNotes { notes: Option<String> },
+    /// Move the routine to another folder
+    MoveToFolder {
+        /// Destination folder ID
+        folder_id: String,
+    },
+    /// Insert an exercise
+    InsertExercise {
+        /// Insert position (1-indexed), defaults to the end
+        position: Option<usize>,
+        exercise: RoutineExerciseArg,
+    },
+    /// Remove an exercise
+    RemoveExercise {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+    },
+    /// Move an exercise to a new position
+    MoveExercise {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+        /// Target position (1-indexed)
+        position: usize,
+    },
+    /// Insert a set template into an exercise
+    AddSet {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+        /// Insert position (1-indexed), defaults to the end
+        position: Option<usize>,
+        set: RoutineSetInputArgs,
+    },
+    /// Remove a set template from an exercise
+    RemoveSet {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+        /// Set position (1-indexed)
+        set_index: usize,
+    },
+    /// Change the rest time of an exercise, omit rest_seconds to clear it
+    SetRestSeconds {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+        /// Rest time between sets in seconds
+        rest_seconds: Option<u32>,
+    },
+    /// Group two or more different exercises into a new superset
+    GroupSuperset {
+        /// Exercise positions (1-indexed)
+        exercise_indexes: Vec<usize>,
+    },
+    /// Remove an exercise from its superset
+    UngroupSuperset {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+    },
+}
+
+impl RoutineEditOp {
+    pub(crate) fn apply(self, routine: &mut RoutineInput) -> Result<(), ToolError> {
+        match self {
+            RoutineEditOp::SetTitle { title } => routine.title = title,
+            RoutineEditOp::SetNotes { notes } => routine.notes = notes,
+            RoutineEditOp::MoveToFolder { folder_id } => routine.folder_id = Some(folder_id),
+            RoutineEditOp::InsertExercise { position, exercise } => {
+                let at = insert_position(position, routine.exercises.len(), "exercise")?;
+                routine
+                    .exercises
+                    .insert(at, RoutineExerciseInput::from(exercise));
+            }
+            RoutineEditOp::RemoveExercise { exercise_index } => {
+                let at =
+                    existing_position(exercise_index, routine.exercises.len(), "exercise_index")?;
+                routine.exercises.remove(at);
+            }
+            RoutineEditOp::MoveExercise {
+                exercise_index,
+                position,
+            } => {
+                let len = routine.exercises.len();
+                let from = existing_position(exercise_index, len, "exercise_index")?;
+                let to = existing_position(position, len, "position")?;
+                let exercise = routine.exercises.remove(from);
+                routine.exercises.insert(to, exercise);
+            }
+            RoutineEditOp::AddSet {
+                exercise_index,
+                position,
+                set,
+            } => {
+                let exercise = exercise_mut(&mut routine.exercises, exercise_index)?;
+                let at = insert_position(position, exercise.sets.len(), "set")?;
+                exercise.sets.insert(at, RoutineSetInput::from(set));
+            }
+            RoutineEditOp::RemoveSet {
+                exercise_index,
+                set_index,
+            } => {
+                let exercise = exercise_mut(&mut routine.exercises, exercise_index)?;
+                let at = existing_position(set_index, exercise.sets.len(), "set_index")?;
+                exercise.sets.remove(at);
+            }
+            RoutineEditOp::SetRestSeconds {
+                exercise_index,
+                rest_seconds,
+            } => {
+                exercise_mut(&mut routine.exercises, exercise_index)?.rest_seconds = rest_seconds;
+            }
+            RoutineEditOp::GroupSuperset { exercise_indexes } => {
+                let exercise_indexes: BTreeSet<usize> = exercise_indexes.into_iter().collect();
+                if exercise_indexes.len() < 2 {
+                    return Err(InvalidParameters(
+                        "a superset needs at least two different exercises".to_string(),
+                    ));
+                }
+                let positions = exercise_indexes
+                    .into_iter()
+                    .map(|exercise_index| {
+                        existing_position(exercise_index, routine.exercises.len(), "exercise_index")
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let superset_id = routine
+                    .exercises
+                    .iter()
+                    .filter_map(|exercise| exercise.superset_id)
+                    .max()
+                    .map_or(0, |id| id + 1);
+                for at in positions {
+                    routine.exercises[at].superset_id = Some(superset_id);
+                }
+            }
+            RoutineEditOp::UngroupSuperset { exercise_index } => {
+                exercise_mut(&mut routine.exercises, exercise_index)?.superset_id = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn exercise_mut(
+    exercises: &mut [RoutineExerciseInput],
+    exercise_index: usize,
+) -> Result<&mut RoutineExerciseInput, ToolError> {
+    let at = existing_position(exercise_index, exercises.len(), "exercise_index")?;
+    Ok(&mut exercises[at])
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_value, json};
+
+    use super::*;
+
+    fn routine() -> RoutineInput {
+        from_value(json!({
+            "title": "Push day",
+            "exercises": [
+                { "exercise_template_id": "bench" },
+                { "exercise_template_id": "fly", "superset_id": 0 },
+                { "exercise_template_id": "dip", "superset_id": 0 },
+                { "exercise_template_id": "push_up" },
+            ],
+        }))
+        .unwrap()
+    }
+
+    fn superset_ids(routine: &RoutineInput) -> Vec<Option<u32>> {
+        routine
+            .exercises
+            .iter()
+            .map(|exercise| exercise.superset_id)
+            .collect()
+    }
+
+    fn group(exercise_indexes: Vec<usize>) -> RoutineEditOp {
+        RoutineEditOp::GroupSuperset { exercise_indexes }
+    }
+
+    #[test]
+    fn grouping_takes_the_next_superset_id() {
+        let mut routine = routine();
+        group(vec![4, 1]).apply(&mut routine).unwrap();
+        assert_eq!(superset_ids(&routine), [Some(1), Some(0), Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn grouping_one_exercise_is_refused() {
+        for exercise_indexes in [vec![], vec![1], vec![1, 1]] {
+            let mut routine = routine();
+            assert!(matches!(
+                group(exercise_indexes).apply(&mut routine),
+                Err(InvalidParameters(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn repeated_exercises_are_grouped_once() {
+        let mut routine = routine();
+        group(vec![1, 4, 1]).apply(&mut routine).unwrap();
+        assert_eq!(superset_ids(&routine), [Some(1), Some(0), Some(0), Some(1)]);
+    }
+
+    #[test]
+    fn grouping_with_a_missing_exercise_changes_nothing() {
+        let mut routine = routine();
+        let before = superset_ids(&routine);
+        for exercise_indexes in [vec![1, 5], vec![0, 1]] {
+            let Err(InvalidParameters(message)) = group(exercise_indexes).apply(&mut routine)
+            else {
+                panic!("the group should be refused");
+            };
+            assert!(message.contains("out of range (1..=4)"), "{}", message);
+        }
+        assert_eq!(superset_ids(&routine), before);
+    }
+
+    #[test]
+    fn ungrouping_clears_the_superset() {
+        let mut routine = routine();
+        RoutineEditOp::UngroupSuperset { exercise_index: 2 }
+            .apply(&mut routine)
+            .unwrap();
+        assert_eq!(superset_ids(&routine), [None, None, Some(0), None]);
+    }
+}
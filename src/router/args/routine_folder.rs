@@ -1,7 +1,10 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct CreateRoutineFolderArgs {
+    /// Folder title
     pub title: String,
+    /// Position of the folder in the list
     pub index: Option<u32>,
 }
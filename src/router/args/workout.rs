@@ -1,20 +1,27 @@
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_with::serde_as;
 
 use crate::hevy::{SetInput, SetType, WorkoutExerciseInput, WorkoutInput};
 
 #[serde_as]
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct WorkoutArgsCommon {
+    /// Workout title
     pub title: String,
+    /// Whether the workout is private
     #[serde(default)]
     pub is_private: bool,
+    /// Start time in ISO 8601 format
     pub start_time: DateTime<Utc>,
+    /// End time in ISO 8601 format
     pub end_time: Option<DateTime<Utc>>,
+    /// Optional description
     pub description: Option<String>,
+    /// Exercises in the workout
     #[serde(default)]
-    #[serde_as(as = "serde_with::DefaultOnNull")]
+    #[serde_as(deserialize_as = "serde_with::DefaultOnNull")]
     pub exercises: Vec<WorkoutExerciseArg>,
 }
 
@@ -23,6 +30,7 @@ impl WorkoutArgsCommon {
         WorkoutInput {
             title: self.title,
             description: self.description,
+            routine_id: None,
             is_private: self.is_private,
             start_time: self.start_time,
             end_time: self.end_time,
@@ -31,38 +39,86 @@ impl WorkoutArgsCommon {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct CreateWorkoutArgs {
     #[serde(flatten)]
     pub workout: WorkoutArgsCommon,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct UpdateWorkoutArgs {
+    /// The workout ID to update
     pub id: String,
     #[serde(flatten)]
     pub workout: WorkoutArgsCommon,
 }
 
 #[serde_as]
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
+pub(crate) struct LogWorkoutFromRoutineArgs {
+    /// The routine ID to log a workout from
+    pub routine_id: String,
+    /// Workout title (defaults to the routine title)
+    pub title: Option<String>,
+    /// Whether the workout is private
+    #[serde(default)]
+    pub is_private: bool,
+    /// Start time in ISO 8601 format
+    pub start_time: DateTime<Utc>,
+    /// End time in ISO 8601 format
+    pub end_time: Option<DateTime<Utc>>,
+    /// Optional description
+    pub description: Option<String>,
+    /// Per-set values actually performed
+    #[serde(default)]
+    #[serde_as(deserialize_as = "serde_with::DefaultOnNull")]
+    pub overrides: Vec<SetOverrideArgs>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub(crate) struct SetOverrideArgs {
+    /// Exercise position in the routine (1-indexed)
+    pub exercise_index: usize,
+    /// Set position within the exercise (1-indexed)
+    pub set_index: usize,
+    /// Weight in kg
+    pub weight_kg: Option<f64>,
+    /// Number of reps
+    pub reps: Option<u32>,
+    /// Rate of perceived exertion: 6, 7, 7.5, 8, 8.5, 9, 9.5 or 10
+    pub rpe: Option<f64>,
+}
+
+#[serde_as]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct WorkoutExerciseArg {
+    /// Exercise template ID
     pub exercise_template_id: String,
+    /// Superset group ID
     pub superset_id: Option<u32>,
+    /// Exercise notes
     pub notes: Option<String>,
+    /// Sets in the exercise
     #[serde(default)]
-    #[serde_as(as = "serde_with::DefaultOnNull")]
+    #[serde_as(deserialize_as = "serde_with::DefaultOnNull")]
     pub sets: Vec<SetInputArgs>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub(crate) struct SetInputArgs {
+    /// Type of set
     pub set_type: Option<SetType>,
+    /// Weight in kg
     pub weight_kg: Option<f64>,
+    /// Number of reps
     pub reps: Option<u32>,
+    /// Duration in seconds
     pub duration_seconds: Option<u32>,
+    /// Distance in meters
     pub distance_meters: Option<f64>,
+    /// Rate of perceived exertion: 6, 7, 7.5, 8, 8.5, 9, 9.5 or 10
     pub rpe: Option<f64>,
+    /// Custom metric value
     pub custom_metric: Option<f64>,
 }
 
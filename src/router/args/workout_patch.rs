@@ -0,0 +1,386 @@
+use chrono::{DateTime, Utc};
+use mcp_spec::handler::ToolError;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_with::serde_as;
+
+use crate::hevy::{SetInput, SetType, WorkoutExerciseInput, WorkoutInput};
+use crate::router::args::common::{existing_position, insert_position};
+use crate::router::args::workout::{SetInputArgs, WorkoutExerciseArg};
+
+#[serde_as]
+#[derive(Deserialize, JsonSchema)]
+pub(crate) struct PatchWorkoutArgs {
+    /// The workout ID to patch
+    pub id: String,
+    /// Operations applied in order
+    #[serde(default)]
+    #[serde_as(deserialize_as = "serde_with::DefaultOnNull")]
+    pub operations: Vec<WorkoutPatchOp>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum WorkoutPatchOp {
+    /// Replace the workout title
+    SetTitle { title: String },
+    /// Replace the description, omit it to clear
+    SetDescription { description: Option<String> },
+    /// Change the start and/or end time (ISO 8601)
+    SetTimes {
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    },
+    /// Change fields of one set; omitted fields are kept, and weight_kg,
+    /// distance_meters, rpe and custom_metric are cleared by null
+    UpdateSet {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+        /// Set position (1-indexed)
+        set_index: usize,
+        set_type: Option<SetType>,
+        /// Weight in kg, null to clear
+        #[serde(default, with = "serde_with::rust::double_option")]
+        #[schemars(with = "Option<f64>")]
+        weight_kg: Option<Option<f64>>,
+        /// Number of reps
+        reps: Option<u32>,
+        /// Duration in seconds
+        duration_seconds: Option<u32>,
+        /// Distance in meters, null to clear
+        #[serde(default, with = "serde_with::rust::double_option")]
+        #[schemars(with = "Option<f64>")]
+        distance_meters: Option<Option<f64>>,
+        /// Rate of perceived exertion: 6, 7, 7.5, 8, 8.5, 9, 9.5 or 10, null to clear
+        #[serde(default, with = "serde_with::rust::double_option")]
+        #[schemars(with = "Option<f64>")]
+        rpe: Option<Option<f64>>,
+        /// Custom metric value, null to clear
+        #[serde(default, with = "serde_with::rust::double_option")]
+        #[schemars(with = "Option<f64>")]
+        custom_metric: Option<Option<f64>>,
+    },
+    /// Insert a set into an exercise
+    AddSet {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+        /// Insert position (1-indexed), defaults to the end
+        position: Option<usize>,
+        set: SetInputArgs,
+    },
+    /// Remove a set from an exercise
+    RemoveSet {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+        /// Set position (1-indexed)
+        set_index: usize,
+    },
+    /// Insert an exercise
+    AddExercise {
+        /// Insert position (1-indexed), defaults to the end
+        position: Option<usize>,
+        exercise: WorkoutExerciseArg,
+    },
+    /// Remove an exercise
+    RemoveExercise {
+        /// Exercise position (1-indexed)
+        exercise_index: usize,
+    },
+}
+
+impl WorkoutPatchOp {
+    pub(crate) fn apply(self, workout: &mut WorkoutInput) -> Result<(), ToolError> {
+        match self {
+            WorkoutPatchOp::SetTitle { title } => workout.title = title,
+            WorkoutPatchOp::SetDescription { description } => workout.description = description,
+            WorkoutPatchOp::SetTimes {
+                start_time,
+                end_time,
+            } => {
+                if let Some(start_time) = start_time {
+                    workout.start_time = start_time;
+                }
+                if let Some(end_time) = end_time {
+                    workout.end_time = Some(end_time);
+                }
+            }
+            WorkoutPatchOp::UpdateSet {
+                exercise_index,
+                set_index,
+                set_type,
+                weight_kg,
+                reps,
+                duration_seconds,
+                distance_meters,
+                rpe,
+                custom_metric,
+            } => {
+                let set = set_mut(&mut workout.exercises, exercise_index, set_index)?;
+                if set_type.is_some() {
+                    set.set_type = set_type;
+                }
+                if let Some(weight_kg) = weight_kg {
+                    set.weight_kg = weight_kg;
+                }
+                if reps.is_some() {
+                    set.reps = reps;
+                }
+                if duration_seconds.is_some() {
+                    set.duration_seconds = duration_seconds;
+                }
+                if let Some(distance_meters) = distance_meters {
+                    set.distance_meters = distance_meters;
+                }
+                if let Some(rpe) = rpe {
+                    set.rpe = rpe;
+                }
+                if let Some(custom_metric) = custom_metric {
+                    set.custom_metric = custom_metric;
+                }
+            }
+            WorkoutPatchOp::AddSet {
+                exercise_index,
+                position,
+                set,
+            } => {
+                let exercise = exercise_mut(&mut workout.exercises, exercise_index)?;
+                let at = insert_position(position, exercise.sets.len(), "set")?;
+                exercise.sets.insert(at, SetInput::from(set));
+            }
+            WorkoutPatchOp::RemoveSet {
+                exercise_index,
+                set_index,
+            } => {
+                let exercise = exercise_mut(&mut workout.exercises, exercise_index)?;
+                let at = existing_position(set_index, exercise.sets.len(), "set_index")?;
+                exercise.sets.remove(at);
+            }
+            WorkoutPatchOp::AddExercise { position, exercise } => {
+                let at = insert_position(position, workout.exercises.len(), "exercise")?;
+                workout
+                    .exercises
+                    .insert(at, WorkoutExerciseInput::from(exercise));
+            }
+            WorkoutPatchOp::RemoveExercise { exercise_index } => {
+                let at =
+                    existing_position(exercise_index, workout.exercises.len(), "exercise_index")?;
+                workout.exercises.remove(at);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn exercise_mut(
+    exercises: &mut [WorkoutExerciseInput],
+    exercise_index: usize,
+) -> Result<&mut WorkoutExerciseInput, ToolError> {
+    let at = existing_position(exercise_index, exercises.len(), "exercise_index")?;
+    Ok(&mut exercises[at])
+}
+
+fn set_mut(
+    exercises: &mut [WorkoutExerciseInput],
+    exercise_index: usize,
+    set_index: usize,
+) -> Result<&mut SetInput, ToolError> {
+    let exercise = exercise_mut(exercises, exercise_index)?;
+    let at = existing_position(set_index, exercise.sets.len(), "set_index")?;
+    Ok(&mut exercise.sets[at])
+}
+
+#[cfg(test)]
+mod tests {
+    use mcp_spec::handler::ToolError::InvalidParameters;
+    use serde_json::{Value, from_value, json};
+
+    use super::*;
+    use crate::router::tools::input_schema;
+
+    fn workout() -> WorkoutInput {
+        from_value(json!({
+            "title": "Push day",
+            "is_private": false,
+            "start_time": "2024-01-01T10:00:00Z",
+            "end_time": "2024-01-01T11:00:00Z",
+            "exercises": [
+                {
+                    "exercise_template_id": "bench",
+                    "sets": [
+                        { "weight_kg": 100.0, "reps": 5, "rpe": 8.0 },
+                        { "weight_kg": 100.0, "reps": 5 },
+                    ],
+                },
+                {
+                    "exercise_template_id": "run",
+                    "sets": [{ "distance_meters": 5000.0, "duration_seconds": 1500, "custom_metric": 2.0 }],
+                },
+            ],
+        }))
+        .unwrap()
+    }
+
+    fn apply(workout: &mut WorkoutInput, op: Value) -> Result<(), ToolError> {
+        from_value::<WorkoutPatchOp>(op).unwrap().apply(workout)
+    }
+
+    fn out_of_range(result: Result<(), ToolError>) -> String {
+        let Err(InvalidParameters(message)) = result else {
+            panic!("the operation should be refused");
+        };
+        message
+    }
+
+    #[test]
+    fn update_set_keeps_omitted_fields() {
+        let mut workout = workout();
+        apply(
+            &mut workout,
+            json!({ "op": "update_set", "exercise_index": 1, "set_index": 1, "reps": 6 }),
+        )
+        .unwrap();
+        let set = &workout.exercises[0].sets[0];
+        assert_eq!(set.reps, Some(6));
+        assert_eq!(set.weight_kg, Some(100.0));
+        assert_eq!(set.rpe, Some(8.0));
+    }
+
+    #[test]
+    fn update_set_clears_fields_set_to_null() {
+        let mut workout = workout();
+        apply(
+            &mut workout,
+            json!({ "op": "update_set", "exercise_index": 1, "set_index": 1, "rpe": null, "weight_kg": null }),
+        )
+        .unwrap();
+        apply(
+            &mut workout,
+            json!({
+                "op": "update_set",
+                "exercise_index": 2,
+                "set_index": 1,
+                "distance_meters": null,
+                "custom_metric": null,
+            }),
+        )
+        .unwrap();
+        let set = &workout.exercises[0].sets[0];
+        assert_eq!((set.rpe, set.weight_kg, set.reps), (None, None, Some(5)));
+        let set = &workout.exercises[1].sets[0];
+        assert_eq!(
+            (set.distance_meters, set.custom_metric, set.duration_seconds),
+            (None, None, Some(1500))
+        );
+    }
+
+    /// The schema of the `update_set` operation, wherever it is nested.
+    fn update_set_schema(schema: &Value) -> Option<&Value> {
+        if schema["properties"]["op"]["const"] == "update_set" {
+            return Some(schema);
+        }
+        match schema {
+            Value::Object(map) => map.values().find_map(update_set_schema),
+            Value::Array(items) => items.iter().find_map(update_set_schema),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn clearable_fields_are_optional_and_nullable_in_the_schema() {
+        let schema = input_schema::<PatchWorkoutArgs>();
+        let update_set = update_set_schema(&schema).expect("update_set is in the schema");
+        for field in ["weight_kg", "distance_meters", "rpe", "custom_metric"] {
+            assert_eq!(
+                update_set["properties"][field]["type"],
+                json!(["number", "null"]),
+                "{}",
+                field
+            );
+            assert!(
+                !update_set["required"]
+                    .as_array()
+                    .is_some_and(|required| required.contains(&json!(field))),
+                "{}",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn sets_and_exercises_are_added_and_removed() {
+        let mut workout = workout();
+        apply(
+            &mut workout,
+            json!({ "op": "add_set", "exercise_index": 1, "position": 1, "set": { "weight_kg": 60.0, "reps": 10 } }),
+        )
+        .unwrap();
+        apply(
+            &mut workout,
+            json!({ "op": "remove_set", "exercise_index": 1, "set_index": 3 }),
+        )
+        .unwrap();
+        let weights: Vec<Option<f64>> = workout.exercises[0]
+            .sets
+            .iter()
+            .map(|set| set.weight_kg)
+            .collect();
+        assert_eq!(weights, [Some(60.0), Some(100.0)]);
+
+        apply(
+            &mut workout,
+            json!({ "op": "add_exercise", "exercise": { "exercise_template_id": "dip", "sets": [] } }),
+        )
+        .unwrap();
+        apply(
+            &mut workout,
+            json!({ "op": "remove_exercise", "exercise_index": 1 }),
+        )
+        .unwrap();
+        let templates: Vec<&str> = workout
+            .exercises
+            .iter()
+            .map(|exercise| exercise.exercise_template_id.as_str())
+            .collect();
+        assert_eq!(templates, ["run", "dip"]);
+    }
+
+    #[test]
+    fn indexes_out_of_range_are_refused() {
+        let mut workout = workout();
+        assert_eq!(
+            out_of_range(apply(
+                &mut workout,
+                json!({ "op": "update_set", "exercise_index": 3, "set_index": 1, "reps": 6 }),
+            )),
+            "exercise_index 3 is out of range (1..=2)"
+        );
+        assert_eq!(
+            out_of_range(apply(
+                &mut workout,
+                json!({ "op": "update_set", "exercise_index": 1, "set_index": 0, "reps": 6 }),
+            )),
+            "set_index 0 is out of range (1..=2)"
+        );
+        assert_eq!(
+            out_of_range(apply(
+                &mut workout,
+                json!({ "op": "remove_set", "exercise_index": 2, "set_index": 2 }),
+            )),
+            "set_index 2 is out of range (1..=1)"
+        );
+        assert_eq!(
+            out_of_range(apply(
+                &mut workout,
+                json!({ "op": "add_set", "exercise_index": 1, "position": 4, "set": { "reps": 5 } }),
+            )),
+            "set position 4 is out of range (1..=3)"
+        );
+        assert_eq!(
+            out_of_range(apply(
+                &mut workout,
+                json!({ "op": "remove_exercise", "exercise_index": 0 }),
+            )),
+            "exercise_index 0 is out of range (1..=2)"
+        );
+    }
+}
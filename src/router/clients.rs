@@ -0,0 +1,212 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tracing::info;
+
+use crate::hevy::HevyClient;
+use crate::tokens::fingerprint;
+
+pub(crate) const DEFAULT_MAX_CLIENTS: usize = 256;
+pub(crate) const DEFAULT_CLIENT_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+struct CachedClient {
+    /// Identifies the entry to its leases; stays put when the client is replaced.
+    id: u64,
+    /// Identifies the client to its unauthorized hook.
+    generation: u64,
+    client: Arc<HevyClient>,
+    sessions: usize,
+    last_used: Instant,
+}
+
+type Entries = DashMap<String, CachedClient>;
+
+/// Hevy clients shared by everything using the same API key, keyed by the
+/// key's fingerprint. Entries held by a live session are never evicted. Idle
+/// ones expire after the idle timeout, and the least recently used idle ones go
+/// first once the cache is full. A client whose key Hevy rejects is dropped
+/// straight away, so the next request starts over with a fresh client; if
+/// sessions still hold the entry, the fresh client is swapped in under their
+/// leases instead.
+pub(crate) struct ClientCache {
+    factory: Arc<ClientFactory>,
+    entries: Arc<Entries>,
+    max_entries: usize,
+    idle_timeout: Duration,
+}
+
+impl ClientCache {
+    pub(crate) fn new(http: reqwest::Client, max_entries: usize, idle_timeout: Duration) -> Self {
+        let entries = Arc::new(DashMap::new());
+        Self {
+            factory: Arc::new(ClientFactory {
+                http,
+                entries: Arc::downgrade(&entries),
+                next_id: AtomicU64::new(1),
+            }),
+            entries,
+            max_entries,
+            idle_timeout,
+        }
+    }
+
+    pub(crate) fn get(&self, api_key: &str) -> Arc<HevyClient> {
+        self.checkout(api_key, false).0
+    }
+
+    /// Keeps the client for `api_key` cached until the lease is dropped.
+    pub(crate) fn lease(&self, api_key: &str) -> ClientLease {
+        let (client, key, id) = self.checkout(api_key, true);
+        ClientLease {
+            entries: Arc::downgrade(&self.entries),
+            key,
+            id,
+            client,
+        }
+    }
+
+    fn checkout(&self, api_key: &str, lease: bool) -> (Arc<HevyClient>, String, u64) {
+        let key = fingerprint(api_key);
+        let now = Instant::now();
+        let mut inserted = false;
+        let (client, id) = {
+            let mut entry = self.entries.entry(key.clone()).or_insert_with(|| {
+                inserted = true;
+                let (client, generation) = self.factory.build(api_key, &key);
+                CachedClient {
+                    id: generation,
+                    generation,
+                    client,
+                    sessions: 0,
+                    last_used: now,
+                }
+            });
+            entry.last_used = now;
+            if lease {
+                entry.sessions += 1;
+            }
+            (entry.client.clone(), entry.id)
+        };
+        if inserted {
+            self.evict(now);
+        }
+        (client, key, id)
+    }
+
+    fn evict(&self, now: Instant) {
+        self.entries.retain(|_, entry| {
+            entry.sessions > 0 || now.duration_since(entry.last_used) < self.idle_timeout
+        });
+
+        let excess = self.entries.len().saturating_sub(self.max_entries);
+        if excess == 0 {
+            return;
+        }
+        let mut idle: Vec<(Instant, String)> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.sessions == 0)
+            .map(|entry| (entry.last_used, entry.key().clone()))
+            .collect();
+        idle.sort();
+        for (_, key) in idle.into_iter().take(excess) {
+            self.entries.remove_if(&key, |_, entry| entry.sessions == 0);
+        }
+    }
+}
+
+struct ClientFactory {
+    http: reqwest::Client,
+    entries: Weak<Entries>,
+    next_id: AtomicU64,
+}
+
+impl ClientFactory {
+    fn build(self: &Arc<Self>, api_key: &str, key: &str) -> (Arc<HevyClient>, u64) {
+        let generation = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let client = HevyClient::with_http_client(self.http.clone(), api_key);
+        let factory = self.clone();
+        let (api_key, key) = (api_key.to_string(), key.to_string());
+        let client = client.on_unauthorized(move || factory.replace(&api_key, &key, generation));
+        (Arc::new(client), generation)
+    }
+
+    fn replace(self: &Arc<Self>, api_key: &str, key: &str, generation: u64) {
+        let Some(entries) = self.entries.upgrade() else {
+            return;
+        };
+        // Only this client's entry; a newer client for the same key gets its own chance.
+        if entries
+            .remove_if(key, |_, entry| {
+                entry.generation == generation && entry.sessions == 0
+            })
+            .is_some()
+        {
+            info!("Dropped a cached Hevy client after its API key was rejected");
+            return;
+        }
+        if let Some(mut entry) = entries.get_mut(key)
+            && entry.generation == generation
+        {
+            let (client, generation) = self.build(api_key, key);
+            entry.client = client;
+            entry.generation = generation;
+            info!(
+                "Replaced a cached Hevy client after its API key was rejected, keeping its {} sessions",
+                entry.sessions
+            );
+        }
+    }
+}
+
+/// A session's claim on a cached client.
+pub(crate) struct ClientLease {
+    entries: Weak<Entries>,
+    key: String,
+    id: u64,
+    /// The client handed out with the lease, used if the cache itself is gone.
+    client: Arc<HevyClient>,
+}
+
+impl ClientLease {
+    /// Identifies the API key without revealing it.
+    pub(crate) fn key_id(&self) -> &str {
+        &self.key
+    }
+
+    /// The entry's current client, which changes if Hevy rejected an earlier one.
+    pub(crate) fn client(&self) -> Arc<HevyClient> {
+        self.entries
+            .upgrade()
+            .and_then(|entries| {
+                entries
+                    .get(&self.key)
+                    .filter(|entry| entry.id == self.id)
+                    .map(|entry| entry.client.clone())
+            })
+            .unwrap_or_else(|| self.client.clone())
+    }
+}
+
+impl Drop for ClientLease {
+    fn drop(&mut self) {
+        let Some(entries) = self.entries.upgrade() else {
+            return;
+        };
+        if let Some(mut entry) = entries.get_mut(&self.key)
+            && entry.id == self.id
+        {
+            entry.sessions = entry.sessions.saturating_sub(1);
+            entry.last_used = Instant::now();
+        }
+    }
+}
+
+impl fmt::Debug for ClientLease {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientLease").field("id", &self.id).finish()
+    }
+}
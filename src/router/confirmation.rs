@@ -0,0 +1,274 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use mcp_spec::Content;
+use mcp_spec::handler::ToolError::{self, InvalidParameters};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::hevy::HevyClient;
+use crate::router::registry::{ToolHandler, call_handler};
+
+const CONFIRMATION_TOKEN_ARG: &str = "confirmation_token";
+const CONFIRMATION_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug)]
+struct PendingConfirmation {
+    tool_name: String,
+    arguments: Value,
+    /// Hash of the preview, which shows the stored workout or routine the
+    /// write builds on.
+    preview_hash: Vec<u8>,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ConfirmationStore {
+    pending: DashMap<String, PendingConfirmation>,
+}
+
+impl ConfirmationStore {
+    fn issue(&self, tool_name: &str, arguments: Value, preview: &[Content]) -> String {
+        let now = Instant::now();
+        self.pending.retain(|_, pending| pending.expires_at > now);
+
+        let token = Uuid::new_v4().simple().to_string();
+        self.pending.insert(
+            token.clone(),
+            PendingConfirmation {
+                tool_name: tool_name.to_string(),
+                arguments,
+                preview_hash: preview_hash(preview),
+                expires_at: now + CONFIRMATION_TTL,
+            },
+        );
+        token
+    }
+
+    /// Uses up `token` and returns the hash of the preview it was issued with.
+    fn redeem(
+        &self,
+        token: &str,
+        tool_name: &str,
+        arguments: &Value,
+    ) -> Result<Vec<u8>, ToolError> {
+        let (_, pending) = self.pending.remove(token).ok_or_else(|| {
+            InvalidParameters(
+                "unknown or already used confirmation_token; call again without it to get a new preview"
+                    .to_string(),
+            )
+        })?;
+
+        if pending.expires_at <= Instant::now() {
+            return Err(InvalidParameters(
+                "confirmation_token has expired; call again without it to get a new preview"
+                    .to_string(),
+            ));
+        }
+        if pending.tool_name != tool_name || &pending.arguments != arguments {
+            return Err(InvalidParameters(
+                "confirmation_token was issued for different arguments; call again without it to get a new preview"
+                    .to_string(),
+            ));
+        }
+        Ok(pending.preview_hash)
+    }
+}
+
+fn preview_hash(preview: &[Content]) -> Vec<u8> {
+    let preview = serde_json::to_vec(preview).unwrap_or_default();
+    Sha256::digest(preview).to_vec()
+}
+
+fn preview(
+    handler: &ToolHandler,
+    client: Arc<HevyClient>,
+    arguments: &Value,
+) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+    let mut preview_arguments = arguments.clone();
+    if let Some(object) = preview_arguments.as_object_mut() {
+        object.insert("dry_run".to_string(), Value::Bool(true));
+    }
+    call_handler(handler, client, preview_arguments)
+}
+
+pub(crate) fn with_confirmation_token_arg(mut schema: Value) -> Value {
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.insert(
+            CONFIRMATION_TOKEN_ARG.to_string(),
+            json!({
+                "type": "string",
+                "description": "Token returned by a previous preview call. The write only happens when the same arguments are sent again with this token."
+            }),
+        );
+    }
+    schema
+}
+
+pub(crate) fn call_tool_with_confirmation(
+    handler: ToolHandler,
+    client: Arc<HevyClient>,
+    store: Arc<ConfirmationStore>,
+    tool_name: &str,
+    arguments: Value,
+) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+    let tool_name = tool_name.to_string();
+
+    Box::pin(async move {
+        let mut arguments = arguments;
+        let token = match arguments.as_object_mut() {
+            Some(object) => object.remove(CONFIRMATION_TOKEN_ARG),
+            None => None,
+        };
+
+        if let Some(token) = token {
+            let token = token.as_str().ok_or_else(|| {
+                InvalidParameters(format!("{} must be a string", CONFIRMATION_TOKEN_ARG))
+            })?;
+            let previewed = store.redeem(token, &tool_name, &arguments)?;
+            // The preview is built again so that a workout or routine changed
+            // since it was shown is not overwritten unseen.
+            let current = preview(&handler, client.clone(), &arguments).await?;
+            if preview_hash(&current) != previewed {
+                return Err(InvalidParameters(
+                    "the data this change is based on has changed since the preview; call again without confirmation_token to get a new preview"
+                        .to_string(),
+                ));
+            }
+            return call_handler(&handler, client, arguments).await;
+        }
+
+        if arguments.get("dry_run").and_then(Value::as_bool) == Some(true) {
+            return call_handler(&handler, client, arguments).await;
+        }
+
+        let mut preview = preview(&handler, client, &arguments).await?;
+
+        let token = store.issue(&tool_name, arguments, &preview);
+        preview.push(Content::text(format!(
+            "Confirmation required: nothing has been written yet. Review the preview above, then call {} again with the same arguments plus \"{}\": \"{}\" within {} seconds to apply it.",
+            tool_name,
+            CONFIRMATION_TOKEN_ARG,
+            token,
+            CONFIRMATION_TTL.as_secs()
+        )));
+        Ok(preview)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    /// A tool that previews the stored `version` and counts its writes.
+    fn tool(version: Arc<AtomicU32>, writes: Arc<AtomicU32>) -> ToolHandler {
+        Arc::new(move |_client, arguments: Value| {
+            let version = version.load(Ordering::SeqCst);
+            let writes = writes.clone();
+            Box::pin(async move {
+                if arguments["dry_run"] == true {
+                    return Ok(format!("preview of version {}", version));
+                }
+                writes.fetch_add(1, Ordering::SeqCst);
+                Ok("written".to_string())
+            })
+        })
+    }
+
+    /// Calls the tool and returns its text, or the error message.
+    async fn call(
+        handler: &ToolHandler,
+        store: &Arc<ConfirmationStore>,
+        arguments: Value,
+    ) -> String {
+        let result = call_tool_with_confirmation(
+            handler.clone(),
+            Arc::new(HevyClient::new("hevy-api-key")),
+            store.clone(),
+            "patch_workout",
+            arguments,
+        )
+        .await;
+        match result {
+            Ok(content) => content
+                .iter()
+                .filter_map(|content| content.as_text())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    fn token(preview: &str) -> String {
+        let (_, rest) = preview.split_once("\"confirmation_token\": \"").unwrap();
+        rest[..32].to_string()
+    }
+
+    #[tokio::test]
+    async fn confirmed_write_goes_through() {
+        let writes = Arc::new(AtomicU32::new(0));
+        let handler = tool(Arc::new(AtomicU32::new(1)), writes.clone());
+        let store = Arc::new(ConfirmationStore::default());
+
+        let preview = call(&handler, &store, json!({ "id": "w1" })).await;
+        assert!(preview.contains("preview of version 1"));
+        assert_eq!(writes.load(Ordering::SeqCst), 0);
+
+        let confirmed = json!({ "id": "w1", "confirmation_token": token(&preview) });
+        assert_eq!(call(&handler, &store, confirmed).await, "written");
+        assert_eq!(writes.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn change_since_the_preview_is_refused() {
+        let version = Arc::new(AtomicU32::new(1));
+        let writes = Arc::new(AtomicU32::new(0));
+        let handler = tool(version.clone(), writes.clone());
+        let store = Arc::new(ConfirmationStore::default());
+
+        let preview = call(&handler, &store, json!({ "id": "w1" })).await;
+        version.store(2, Ordering::SeqCst);
+        let confirmed = json!({ "id": "w1", "confirmation_token": token(&preview) });
+        let refused = call(&handler, &store, confirmed.clone()).await;
+        assert!(
+            refused.contains("has changed since the preview"),
+            "{}",
+            refused
+        );
+        assert_eq!(writes.load(Ordering::SeqCst), 0);
+
+        // The token is used up; a new preview shows the change.
+        assert!(
+            call(&handler, &store, confirmed)
+                .await
+                .contains("already used")
+        );
+        assert!(
+            call(&handler, &store, json!({ "id": "w1" }))
+                .await
+                .contains("preview of version 2")
+        );
+    }
+
+    #[tokio::test]
+    async fn token_is_bound_to_its_arguments() {
+        let writes = Arc::new(AtomicU32::new(0));
+        let handler = tool(Arc::new(AtomicU32::new(1)), writes.clone());
+        let store = Arc::new(ConfirmationStore::default());
+
+        let preview = call(&handler, &store, json!({ "id": "w1" })).await;
+        let other = json!({ "id": "w2", "confirmation_token": token(&preview) });
+        assert!(
+            call(&handler, &store, other)
+                .await
+                .contains("issued for different arguments")
+        );
+        assert_eq!(writes.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,3 +1,5 @@
+use serde_json::{Value, to_string_pretty};
+
 use crate::hevy::{ExerciseTemplate, Routine, RoutineFolder, Workout};
 
 pub(crate) fn format_workout(workout: &Workout) -> String {
@@ -38,6 +40,12 @@ pub(crate) fn format_workout(workout: &Workout) -> String {
             if let Some(d) = set.duration_seconds {
                 set_info.push_str(&format!("{}s ", d));
             }
+            if let Some(m) = set.distance_meters {
+                set_info.push_str(&format!("{:.0}m ", m));
+            }
+            if let Some(rpe) = set.rpe {
+                set_info.push_str(&format!("@RPE {} ", rpe));
+            }
             output.push_str(&format!("{}\n", set_info.trim()));
         }
     }
@@ -66,10 +74,34 @@ pub(crate) fn format_routine(routine: &Routine) -> String {
             exercise.index + 1,
             exercise.exercise_template_id
         ));
+        if let Some(superset_id) = exercise.superset_id {
+            output.push_str(&format!("     Superset: {}\n", superset_id));
+        }
+        if let Some(rest) = exercise.rest_seconds {
+            output.push_str(&format!("     Rest: {}s\n", rest));
+        }
         if let Some(notes) = &exercise.notes {
             output.push_str(&format!("     Notes: {}\n", notes));
         }
         output.push_str(&format!("     Sets: {}\n", exercise.sets.len()));
+        for set in &exercise.sets {
+            let mut set_info = format!("     Set {}: ", set.index + 1);
+            if let Some(w) = set.weight_kg {
+                set_info.push_str(&format!("{:.1}kg ", w));
+            }
+            if let Some(r) = set.reps {
+                set_info.push_str(&format!("x{} reps ", r));
+            } else if let Some(range) = &set.rep_range {
+                set_info.push_str(&format!("x{}-{} reps ", range.start, range.end));
+            }
+            if let Some(d) = set.duration_seconds {
+                set_info.push_str(&format!("{}s ", d));
+            }
+            if let Some(m) = set.distance_meters {
+                set_info.push_str(&format!("{:.0}m ", m));
+            }
+            output.push_str(&format!("{}\n", set_info.trim()));
+        }
     }
 
     output
@@ -115,3 +147,48 @@ pub(crate) fn format_folder(folder: &RoutineFolder) -> String {
 
     output
 }
+
+pub(crate) fn format_diff(before: &str, after: &str) -> String {
+    let old: Vec<&str> = before.lines().collect();
+    let new: Vec<&str> = after.lines().collect();
+
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut output = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            output.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            output.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            output.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+    output
+}
+
+/// Stands in for the id Hevy would assign to something a dry run creates.
+pub(crate) const DRY_RUN_ID: &str = "(assigned by Hevy)";
+
+/// `preview` is what would be saved, rendered by the entity's usual formatter.
+pub(crate) fn format_dry_run(preview: &str, body: &Value) -> String {
+    format!(
+        "Dry run: nothing was sent to Hevy.\n\n{}\nRequest body:\n{}\n",
+        preview,
+        to_string_pretty(body).unwrap_or_else(|_| body.to_string())
+    )
+}
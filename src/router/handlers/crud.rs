@@ -6,18 +6,25 @@ use serde::de::DeserializeOwned;
 use serde_json::Value;
 
 use crate::hevy::HevyClient;
-use crate::router::args::parse_args;
+use crate::router::args::{DryRunArgs, parse_args};
+use crate::router::formatters::{DRY_RUN_ID, format_dry_run};
 
 pub(crate) trait CrudHandler {
     type CreateArgs: DeserializeOwned;
     type UpdateArgs: DeserializeOwned;
-    type Input;
+    type Input: Send + Sync;
     type Output;
 
     fn entity_name() -> &'static str;
+    fn request_body(input: &Self::Input) -> Value;
     fn format(output: &Self::Output) -> String;
+    fn preview(input: &Self::Input, id: &str) -> Self::Output;
     fn extract_create(args: Self::CreateArgs) -> Self::Input;
     fn extract_update(args: Self::UpdateArgs) -> (String, Self::Input);
+    fn validate<'a>(
+        client: &'a HevyClient,
+        input: &'a Self::Input,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ToolError>> + Send + 'a>>;
     fn create<'a>(
         client: &'a HevyClient,
         input: Self::Input,
@@ -35,8 +42,16 @@ pub(crate) trait CrudHandler {
         Self: Sized,
     {
         Box::pin(async move {
+            let DryRunArgs { dry_run } = parse_args(args.clone())?;
             let parsed_args: Self::CreateArgs = parse_args(args.clone())?;
             let input = Self::extract_create(parsed_args);
+            Self::validate(client, &input).await?;
+            if dry_run {
+                return Ok(format_dry_run(
+                    &Self::format(&Self::preview(&input, DRY_RUN_ID)),
+                    &Self::request_body(&input),
+                ));
+            }
 
             let created = Self::create(client, input).await.map_err(|e| {
                 ExecutionError(format!("Error creating {}: {}", Self::entity_name(), e))
@@ -57,8 +72,16 @@ pub(crate) trait CrudHandler {
         Self: Sized,
     {
         Box::pin(async move {
+            let DryRunArgs { dry_run } = parse_args(args.clone())?;
             let parsed_args: Self::UpdateArgs = parse_args(args.clone())?;
             let (id, input) = Self::extract_update(parsed_args);
+            Self::validate(client, &input).await?;
+            if dry_run {
+                return Ok(format_dry_run(
+                    &Self::format(&Self::preview(&input, &id)),
+                    &Self::request_body(&input),
+                ));
+            }
 
             let updated = Self::update(client, &id, input).await.map_err(|e| {
                 ExecutionError(format!("Error updating {}: {}", Self::entity_name(), e))
@@ -1,10 +1,13 @@
 use mcp_spec::handler::ToolError::{self, ExecutionError};
 use serde_json::Value;
 
+use crate::hevy::client::routine_folder_body;
 use crate::hevy::types::RoutineFoldersResponse;
 use crate::hevy::{HevyClient, RoutineFolderInput};
-use crate::router::args::{CreateRoutineFolderArgs, IdArgs, PaginationArgs, parse_args};
-use crate::router::formatters::format_folder;
+use crate::router::args::{
+    CreateRoutineFolderArgs, DryRunArgs, IdArgs, PaginationArgs, parse_args,
+};
+use crate::router::formatters::{DRY_RUN_ID, format_dry_run, format_folder};
 
 pub(crate) async fn handle_get_routine_folders(
     client: &HevyClient,
@@ -35,12 +38,21 @@ pub(crate) async fn handle_create_routine_folder(
     client: &HevyClient,
     args: &Value,
 ) -> Result<String, ToolError> {
+    let DryRunArgs { dry_run } = parse_args(args.clone())?;
     let args: CreateRoutineFolderArgs = parse_args(args.clone())?;
+    let input = RoutineFolderInput {
+        title: args.title,
+        index: args.index,
+    };
+    if dry_run {
+        return Ok(format_dry_run(
+            &format_folder(&input.preview(DRY_RUN_ID)),
+            &routine_folder_body(&input),
+        ));
+    }
+
     let folder = client
-        .create_routine_folder(RoutineFolderInput {
-            title: args.title,
-            index: args.index,
-        })
+        .create_routine_folder(input)
         .await
         .map_err(|e| ExecutionError(format!("Error creating Folder: {}", e)))?;
     Ok(format_created_folder(&folder))
@@ -1,15 +1,18 @@
 use std::future::Future;
 use std::pin::Pin;
 
-use mcp_spec::handler::ToolError::{self, ExecutionError};
+use mcp_spec::handler::ToolError::{self, ExecutionError, InvalidParameters};
 use serde_json::Value;
 
+use crate::hevy::client::routine_body;
 use crate::hevy::types::RoutinesResponse;
 use crate::hevy::{HevyClient, Routine, RoutineInput};
 use crate::router::args::{
-    CreateRoutineArgs, IdArgs, PaginationArgs, UpdateRoutineArgs, parse_args,
+    CreateRoutineArgs, DryRunArgs, EditRoutineArgs, IdArgs, PaginationArgs, UpdateRoutineArgs,
+    parse_args,
 };
-use crate::router::formatters::format_routine;
+use crate::router::formatters::{format_diff, format_dry_run, format_routine};
+use crate::router::validation::validate_routine;
 
 use crate::router::handlers::crud::CrudHandler;
 
@@ -25,10 +28,18 @@ impl CrudHandler for RoutineHandler {
         "Routine"
     }
 
+    fn request_body(input: &Self::Input) -> Value {
+        routine_body(input)
+    }
+
     fn format(output: &Self::Output) -> String {
         format_routine(output)
     }
 
+    fn preview(input: &Self::Input, id: &str) -> Self::Output {
+        input.preview(id)
+    }
+
     fn extract_create(args: Self::CreateArgs) -> Self::Input {
         args.routine.into_input()
     }
@@ -37,6 +48,13 @@ impl CrudHandler for RoutineHandler {
         (args.id, args.routine.into_input())
     }
 
+    fn validate<'a>(
+        client: &'a HevyClient,
+        input: &'a Self::Input,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ToolError>> + Send + 'a>> {
+        Box::pin(validate_routine(client, input, &[]))
+    }
+
     fn create<'a>(
         client: &'a HevyClient,
         input: Self::Input,
@@ -93,6 +111,54 @@ pub(crate) async fn handle_update_routine(
     RoutineHandler::handle_update(client, args).await
 }
 
+pub(crate) async fn handle_edit_routine(
+    client: &HevyClient,
+    args: &Value,
+) -> Result<String, ToolError> {
+    let DryRunArgs { dry_run } = parse_args(args.clone())?;
+    let args: EditRoutineArgs = parse_args(args.clone())?;
+    if args.operations.is_empty() {
+        return Err(InvalidParameters(
+            "operations must contain at least one operation".to_string(),
+        ));
+    }
+
+    let before = client
+        .get_routine(&args.id)
+        .await
+        .map_err(|e| ExecutionError(format!("Error fetching routine: {}", e)))?;
+
+    let stored = RoutineInput::from(&before);
+    let mut input = stored.clone();
+    for (index, operation) in args.operations.into_iter().enumerate() {
+        operation
+            .apply(&mut input)
+            .map_err(|e| InvalidParameters(format!("operations[{}]: {}", index, e)))?;
+    }
+    validate_routine(client, &input, &stored.exercises).await?;
+    if dry_run {
+        let after = format_routine(&input.preview(&args.id));
+        return Ok(format_dry_run(
+            &format!(
+                "Changes:\n{}\n{}",
+                format_diff(&format_routine(&before), &after),
+                after
+            ),
+            &routine_body(&input),
+        ));
+    }
+
+    let after = client
+        .update_routine(&args.id, input)
+        .await
+        .map_err(|e| ExecutionError(format!("Error updating Routine: {}", e)))?;
+    Ok(format!(
+        "Routine edited successfully!\n\nChanges:\n{}\n{}",
+        format_diff(&format_routine(&before), &format_routine(&after)),
+        format_routine(&after)
+    ))
+}
+
 fn format_routines_page(response: &crate::hevy::PaginatedResponse<RoutinesResponse>) -> String {
     let mut output = format!("Page {} of {}\n\n", response.page, response.page_count);
     if response.data.routines.is_empty() {
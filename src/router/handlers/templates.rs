@@ -1,10 +1,13 @@
 use mcp_spec::handler::ToolError::{self, ExecutionError};
 use serde_json::Value;
 
+use crate::hevy::client::exercise_template_body;
 use crate::hevy::types::ExerciseTemplatesResponse;
 use crate::hevy::{CreateExerciseTemplateInput, HevyClient};
-use crate::router::args::{CreateExerciseTemplateArgs, IdArgs, PaginationArgs, parse_args};
-use crate::router::formatters::format_exercise_template;
+use crate::router::args::{
+    CreateExerciseTemplateArgs, DryRunArgs, IdArgs, PaginationArgs, parse_args,
+};
+use crate::router::formatters::{DRY_RUN_ID, format_dry_run, format_exercise_template};
 
 pub(crate) async fn handle_get_exercise_templates(
     client: &HevyClient,
@@ -36,15 +39,24 @@ pub(crate) async fn handle_create_exercise_template(
     client: &HevyClient,
     args: &Value,
 ) -> Result<String, ToolError> {
+    let DryRunArgs { dry_run } = parse_args(args.clone())?;
     let args: CreateExerciseTemplateArgs = parse_args(args.clone())?;
+    let input = CreateExerciseTemplateInput {
+        title: args.title,
+        exercise_type: args.exercise_type,
+        equipment_category: args.equipment_category,
+        muscle_group: args.muscle_group,
+        other_muscles: args.other_muscles,
+    };
+    if dry_run {
+        return Ok(format_dry_run(
+            &format_exercise_template(&input.preview(DRY_RUN_ID)),
+            &exercise_template_body(&input),
+        ));
+    }
+
     let created = client
-        .create_exercise_template(CreateExerciseTemplateInput {
-            title: args.title,
-            exercise_type: args.exercise_type,
-            equipment_category: args.equipment_category,
-            muscle_group: args.muscle_group,
-            other_muscles: args.other_muscles,
-        })
+        .create_exercise_template(input)
         .await
         .map_err(|e| ExecutionError(format!("Error creating exercise template: {}", e)))?;
 
@@ -1,15 +1,18 @@
 use std::future::Future;
 use std::pin::Pin;
 
-use mcp_spec::handler::ToolError::{self, ExecutionError};
+use mcp_spec::handler::ToolError::{self, ExecutionError, InvalidParameters};
 use serde_json::Value;
 
+use crate::hevy::client::workout_body;
 use crate::hevy::types::WorkoutsResponse;
-use crate::hevy::{HevyClient, Workout, WorkoutInput};
+use crate::hevy::{HevyClient, Workout, WorkoutExerciseInput, WorkoutInput};
 use crate::router::args::{
-    CreateWorkoutArgs, IdArgs, PaginationArgs, UpdateWorkoutArgs, WorkoutEventsArgs, parse_args,
+    CreateWorkoutArgs, DryRunArgs, IdArgs, LogWorkoutFromRoutineArgs, PaginationArgs,
+    PatchWorkoutArgs, SetOverrideArgs, UpdateWorkoutArgs, WorkoutEventsArgs, parse_args,
 };
-use crate::router::formatters::format_workout;
+use crate::router::formatters::{DRY_RUN_ID, format_diff, format_dry_run, format_workout};
+use crate::router::validation::validate_workout;
 
 use crate::router::handlers::crud::CrudHandler;
 
@@ -25,10 +28,18 @@ impl CrudHandler for WorkoutHandler {
         "Workout"
     }
 
+    fn request_body(input: &Self::Input) -> Value {
+        workout_body(input)
+    }
+
     fn format(output: &Self::Output) -> String {
         format_workout(output)
     }
 
+    fn preview(input: &Self::Input, id: &str) -> Self::Output {
+        input.preview(id)
+    }
+
     fn extract_create(args: Self::CreateArgs) -> Self::Input {
         args.workout.into_input()
     }
@@ -37,6 +48,13 @@ impl CrudHandler for WorkoutHandler {
         (args.id, args.workout.into_input())
     }
 
+    fn validate<'a>(
+        client: &'a HevyClient,
+        input: &'a Self::Input,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ToolError>> + Send + 'a>> {
+        Box::pin(validate_workout(client, input, &[]))
+    }
+
     fn create<'a>(
         client: &'a HevyClient,
         input: Self::Input,
@@ -113,6 +131,137 @@ pub(crate) async fn handle_update_workout(
     WorkoutHandler::handle_update(client, args).await
 }
 
+pub(crate) async fn handle_patch_workout(
+    client: &HevyClient,
+    args: &Value,
+) -> Result<String, ToolError> {
+    let DryRunArgs { dry_run } = parse_args(args.clone())?;
+    let args: PatchWorkoutArgs = parse_args(args.clone())?;
+    if args.operations.is_empty() {
+        return Err(InvalidParameters(
+            "operations must contain at least one operation".to_string(),
+        ));
+    }
+
+    let before = client
+        .get_workout(&args.id)
+        .await
+        .map_err(|e| ExecutionError(format!("Error fetching workout: {}", e)))?;
+
+    let stored = WorkoutInput::from(&before);
+    let mut input = stored.clone();
+    for (index, operation) in args.operations.into_iter().enumerate() {
+        operation
+            .apply(&mut input)
+            .map_err(|e| InvalidParameters(format!("operations[{}]: {}", index, e)))?;
+    }
+    validate_workout(client, &input, &stored.exercises).await?;
+    if dry_run {
+        let after = format_workout(&input.preview(&args.id));
+        return Ok(format_dry_run(
+            &format!(
+                "Changes:\n{}\n{}",
+                format_diff(&format_workout(&before), &after),
+                after
+            ),
+            &workout_body(&input),
+        ));
+    }
+
+    let after = client
+        .update_workout(&args.id, input)
+        .await
+        .map_err(|e| ExecutionError(format!("Error updating Workout: {}", e)))?;
+    Ok(format!(
+        "Workout patched successfully!\n\nChanges:\n{}\n{}",
+        format_diff(&format_workout(&before), &format_workout(&after)),
+        format_workout(&after)
+    ))
+}
+
+pub(crate) async fn handle_log_workout_from_routine(
+    client: &HevyClient,
+    args: &Value,
+) -> Result<String, ToolError> {
+    let DryRunArgs { dry_run } = parse_args(args.clone())?;
+    let args: LogWorkoutFromRoutineArgs = parse_args(args.clone())?;
+
+    let routine = client
+        .get_routine(&args.routine_id)
+        .await
+        .map_err(|e| ExecutionError(format!("Error fetching routine: {}", e)))?;
+
+    let copied: Vec<WorkoutExerciseInput> = routine.exercises.iter().map(Into::into).collect();
+    let mut exercises = copied.clone();
+    for set_override in &args.overrides {
+        apply_set_override(&mut exercises, set_override)?;
+    }
+
+    let input = WorkoutInput {
+        title: args.title.unwrap_or_else(|| routine.title.clone()),
+        description: args.description,
+        routine_id: Some(routine.id.clone()),
+        is_private: args.is_private,
+        start_time: args.start_time,
+        end_time: args.end_time,
+        exercises,
+    };
+    validate_workout(client, &input, &copied).await?;
+    if dry_run {
+        return Ok(format_dry_run(
+            &format_workout(&input.preview(DRY_RUN_ID)),
+            &workout_body(&input),
+        ));
+    }
+
+    let created = client
+        .create_workout(input)
+        .await
+        .map_err(|e| ExecutionError(format!("Error creating Workout: {}", e)))?;
+    Ok(format!(
+        "Workout created successfully from routine {}!\n\n{}",
+        routine.title,
+        format_workout(&created)
+    ))
+}
+
+fn apply_set_override(
+    exercises: &mut [WorkoutExerciseInput],
+    set_override: &SetOverrideArgs,
+) -> Result<(), ToolError> {
+    let exercise = set_override
+        .exercise_index
+        .checked_sub(1)
+        .and_then(|index| exercises.get_mut(index))
+        .ok_or_else(|| {
+            InvalidParameters(format!(
+                "override exercise_index {} is out of range",
+                set_override.exercise_index
+            ))
+        })?;
+    let set = set_override
+        .set_index
+        .checked_sub(1)
+        .and_then(|index| exercise.sets.get_mut(index))
+        .ok_or_else(|| {
+            InvalidParameters(format!(
+                "override set_index {} is out of range for exercise {}",
+                set_override.set_index, set_override.exercise_index
+            ))
+        })?;
+
+    if let Some(weight_kg) = set_override.weight_kg {
+        set.weight_kg = Some(weight_kg);
+    }
+    if let Some(reps) = set_override.reps {
+        set.reps = Some(reps);
+    }
+    if let Some(rpe) = set_override.rpe {
+        set.rpe = Some(rpe);
+    }
+    Ok(())
+}
+
 fn format_workouts_page(response: &crate::hevy::PaginatedResponse<WorkoutsResponse>) -> String {
     let mut output = format!("Page {} of {}\n\n", response.page, response.page_count);
     if response.data.workouts.is_empty() {
@@ -1,59 +1,438 @@
+use std::collections::HashSet;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
-use dashmap::DashMap;
-use mcp_server::router::{CapabilitiesBuilder, Router};
+use mcp_server::BoxError;
+use mcp_server::router::{CapabilitiesBuilder, Router, RouterService};
 use mcp_spec::handler::{PromptError, ResourceError, ToolError};
-use mcp_spec::prompt::Prompt;
-use mcp_spec::protocol::ServerCapabilities;
+use mcp_spec::prompt::{Prompt, PromptMessageContent};
+use mcp_spec::protocol::{
+    ErrorData, INTERNAL_ERROR, INVALID_PARAMS, JsonRpcNotification, JsonRpcRequest,
+    JsonRpcResponse, METHOD_NOT_FOUND, ServerCapabilities,
+};
 use mcp_spec::{Content, Resource, Tool};
-use serde_json::Value;
+use serde_json::{Map, Value, json};
+use tower_service::Service;
+use tracing::warn;
 
 use crate::hevy::HevyClient;
+use crate::hevy::client::build_http_client;
 
 mod args;
+mod clients;
+mod confirmation;
 mod formatters;
 mod handlers;
+mod policy;
+mod prompts;
+mod protocol;
+mod registry;
+mod resources;
+mod subscriptions;
 mod tools;
+mod validation;
 
-use crate::router::handlers::call_tool;
-use crate::router::tools::list_tools;
+pub(crate) use crate::router::clients::ClientLease;
+use crate::router::clients::{ClientCache, DEFAULT_CLIENT_IDLE_TIMEOUT, DEFAULT_MAX_CLIENTS};
+pub(crate) use crate::router::confirmation::ConfirmationStore;
+use crate::router::confirmation::{call_tool_with_confirmation, with_confirmation_token_arg};
+pub use crate::router::policy::ToolPolicy;
+use crate::router::prompts::{get_prompt, list_prompts};
+pub use crate::router::protocol::ProtocolVersion;
+use crate::router::registry::call_handler;
+pub use crate::router::registry::{
+    ToolAnnotations, ToolDefinition, ToolFuture, ToolHandler, ToolRegistry,
+};
+use crate::router::resources::{
+    JSON_MIME_TYPE, list_resource_templates, list_resources, read_resource,
+};
+pub(crate) use crate::router::subscriptions::ResourceSubscriptions;
+pub use crate::router::tools::input_schema;
+
+const RESOURCE_NOT_FOUND: i32 = -32002;
+
+/// Delivers server-initiated notifications to whichever transport owns the session.
+pub(crate) type NotificationSink = Arc<dyn Fn(JsonRpcNotification) + Send + Sync>;
 
 #[derive(Clone)]
 pub struct HevyRouter {
-    client_cache: Arc<DashMap<String, Arc<HevyClient>>>,
+    http_client: reqwest::Client,
+    max_clients: usize,
+    client_idle_timeout: Duration,
+    client_cache: Arc<ClientCache>,
+    tools: Arc<ToolRegistry>,
+    confirmation_tools: Arc<HashSet<String>>,
+    read_only: bool,
+    poll_interval: Duration,
     pub default_api_key: Option<String>,
 }
 
 impl HevyRouter {
     pub fn new(default_api_key: Option<String>) -> Self {
+        let http_client = build_http_client();
         Self {
-            client_cache: Arc::new(DashMap::new()),
+            client_cache: Arc::new(ClientCache::new(
+                http_client.clone(),
+                DEFAULT_MAX_CLIENTS,
+                DEFAULT_CLIENT_IDLE_TIMEOUT,
+            )),
+            http_client,
+            max_clients: DEFAULT_MAX_CLIENTS,
+            client_idle_timeout: DEFAULT_CLIENT_IDLE_TIMEOUT,
+            tools: Arc::new(ToolRegistry::builtin()),
+            confirmation_tools: Arc::new(HashSet::new()),
+            read_only: false,
+            poll_interval: Duration::from_secs(60),
             default_api_key,
         }
     }
 
+    pub fn with_tool(mut self, tool: ToolDefinition) -> Self {
+        Arc::make_mut(&mut self.tools).register(tool);
+        self
+    }
+
+    pub fn tools(&self) -> &ToolRegistry {
+        &self.tools
+    }
+
+    /// Requires two-step confirmation for `tools`. The first step is a dry run,
+    /// so each tool must be registered already and support `dry_run`.
+    pub fn with_confirmation_tools<I, S>(mut self, tools: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut confirmation_tools = HashSet::new();
+        for name in tools.into_iter().map(Into::into) {
+            match self.tools.get(&name) {
+                None => return Err(format!("Unknown tool: {}", name)),
+                Some(tool) if !tool.supports_dry_run() => {
+                    return Err(format!(
+                        "{} does not support dry_run, so it cannot require confirmation",
+                        name
+                    ));
+                }
+                Some(_) => {
+                    confirmation_tools.insert(name);
+                }
+            }
+        }
+        self.confirmation_tools = Arc::new(confirmation_tools);
+        Ok(self)
+    }
+
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn requires_confirmation(&self, tool_name: &str) -> bool {
+        self.confirmation_tools.contains(tool_name)
+    }
+
+    pub fn has_confirmation_tools(&self) -> bool {
+        !self.confirmation_tools.is_empty()
+    }
+
+    /// Caps the cached Hevy clients at `max_clients` and drops idle ones
+    /// after `idle_timeout`.
+    pub fn with_client_cache(mut self, max_clients: usize, idle_timeout: Duration) -> Self {
+        self.max_clients = max_clients;
+        self.client_idle_timeout = idle_timeout;
+        self.rebuild_client_cache();
+        self
+    }
+
+    /// Sends every Hevy request through `http_client`, whose connection pool
+    /// is shared by all API keys.
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = http_client;
+        self.rebuild_client_cache();
+        self
+    }
+
+    pub fn http_client(&self) -> &reqwest::Client {
+        &self.http_client
+    }
+
+    fn rebuild_client_cache(&mut self) {
+        self.client_cache = Arc::new(ClientCache::new(
+            self.http_client.clone(),
+            self.max_clients,
+            self.client_idle_timeout,
+        ));
+    }
+
     pub fn get_or_create_client(&self, api_key: &str) -> Arc<HevyClient> {
-        self.client_cache
-            .entry(api_key.to_string())
-            .or_insert_with(|| Arc::new(HevyClient::new(api_key)))
-            .clone()
+        self.client_cache.get(api_key)
+    }
+
+    pub(crate) fn lease_client(&self, api_key: &str) -> ClientLease {
+        self.client_cache.lease(api_key)
     }
 }
 
 #[derive(Clone)]
 pub(crate) struct RequestRouter {
     hevy_router: Arc<HevyRouter>,
-    api_key: String,
+    client: Arc<ClientLease>,
+    confirmations: Arc<ConfirmationStore>,
+    policy: Arc<ToolPolicy>,
+    /// `None` when the session cannot take resource subscriptions.
+    subscriptions: Option<Arc<ResourceSubscriptions>>,
+    notify: Option<NotificationSink>,
+    protocol_version: ProtocolVersion,
 }
 
 impl RequestRouter {
-    pub(crate) fn new(hevy_router: Arc<HevyRouter>, api_key: String) -> Self {
+    pub(crate) fn new(
+        hevy_router: Arc<HevyRouter>,
+        client: Arc<ClientLease>,
+        confirmations: Arc<ConfirmationStore>,
+        policy: Arc<ToolPolicy>,
+        subscriptions: Arc<ResourceSubscriptions>,
+    ) -> Self {
         Self {
             hevy_router,
-            api_key,
+            client,
+            confirmations,
+            policy,
+            subscriptions: Some(subscriptions),
+            notify: None,
+            protocol_version: ProtocolVersion::LATEST,
+        }
+    }
+
+    pub(crate) fn with_protocol_version(mut self, protocol_version: ProtocolVersion) -> Self {
+        self.protocol_version = protocol_version;
+        self
+    }
+
+    /// Stops advertising and accepting `resources/subscribe`, for sessions
+    /// whose later requests may not reach this process.
+    pub(crate) fn without_subscriptions(mut self) -> Self {
+        self.subscriptions = None;
+        self
+    }
+
+    /// Sends notifications about the request being handled, such as progress, to `notify`.
+    pub(crate) fn with_notifications(mut self, notify: NotificationSink) -> Self {
+        self.notify = Some(notify);
+        self
+    }
+
+    fn is_tool_enabled(&self, tool: &ToolDefinition) -> bool {
+        if self.hevy_router.read_only && !tool.is_read_only() {
+            return false;
+        }
+        self.policy.permits(tool)
+    }
+
+    fn list_annotated_tools(&self) -> Vec<ToolDefinition> {
+        self.hevy_router
+            .tools
+            .iter()
+            .filter(|tool| self.is_tool_enabled(tool))
+            .cloned()
+            .map(|mut tool| {
+                if self.hevy_router.requires_confirmation(tool.name()) {
+                    tool.tool.input_schema = with_confirmation_token_arg(tool.tool.input_schema);
+                }
+                tool
+            })
+            .collect()
+    }
+
+    pub(crate) fn handle_request(
+        &self,
+        request: JsonRpcRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<JsonRpcResponse, BoxError>> + Send>> {
+        match request.method.as_str() {
+            "tools/list" => {
+                let tools: Vec<Value> = self
+                    .list_annotated_tools()
+                    .iter()
+                    .map(|tool| self.describe_tool(tool))
+                    .collect();
+                let result = json!({ "tools": tools });
+                Box::pin(async move { Ok(rpc_response(request.id, Ok(result))) })
+            }
+            "resources/templates/list" => {
+                let result = json!({ "resourceTemplates": list_resource_templates() });
+                Box::pin(async move { Ok(rpc_response(request.id, Ok(result))) })
+            }
+            "resources/read" => {
+                let this = self.clone();
+                Box::pin(async move {
+                    let result = this.handle_resources_read(request.params).await;
+                    Ok(rpc_response(request.id, result))
+                })
+            }
+            "prompts/get" => {
+                let this = self.clone();
+                Box::pin(async move {
+                    let result = this.handle_prompts_get(request.params).await;
+                    Ok(rpc_response(request.id, result))
+                })
+            }
+            "initialize" => {
+                let protocol_version = self.protocol_version;
+                let response = RouterService(self.clone()).call(request);
+                Box::pin(async move {
+                    let mut response = response.await?;
+                    if let Some(result) = response.result.as_mut() {
+                        result["protocolVersion"] = json!(protocol_version.as_str());
+                    }
+                    Ok(response)
+                })
+            }
+            "tools/call" => self.handle_tools_call(request),
+            "resources/subscribe" | "resources/unsubscribe" => {
+                let result = self.handle_resources_subscription(&request);
+                Box::pin(async move { Ok(rpc_response(request.id, result)) })
+            }
+            _ => RouterService(self.clone()).call(request),
+        }
+    }
+
+    /// Serializes a tool with only the fields the session's protocol version knows about.
+    fn describe_tool(&self, tool: &ToolDefinition) -> Value {
+        let mut value = json!(tool);
+        if let Value::Object(fields) = &mut value {
+            if !self.protocol_version.has_tool_annotations() {
+                fields.remove("annotations");
+            }
+            if !self.protocol_version.has_tool_titles() {
+                fields.remove("title");
+            }
+            if !self.protocol_version.has_structured_output() {
+                fields.remove("outputSchema");
+            }
+        }
+        value
+    }
+
+    fn handle_tools_call(
+        &self,
+        request: JsonRpcRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<JsonRpcResponse, BoxError>> + Send>> {
+        let progress = progress_token(&request).zip(self.notify.clone());
+        let structured = self.protocol_version.has_structured_output()
+            && request
+                .params
+                .as_ref()
+                .and_then(|params| params.get("name"))
+                .and_then(Value::as_str)
+                .and_then(|name| self.hevy_router.tools.get(name))
+                .is_some_and(|tool| tool.output_schema.is_some());
+
+        let response = RouterService(self.clone()).call(request);
+        Box::pin(async move {
+            if let Some((token, notify)) = &progress {
+                notify(progress_notification(token, 0));
+            }
+            let response = response.await;
+            if let Some((token, notify)) = &progress {
+                notify(progress_notification(token, 1));
+            }
+            let mut response = response?;
+            if structured {
+                add_structured_content(&mut response);
+            }
+            Ok(response)
+        })
+    }
+
+    /// Like `handle_request`, but folds dispatch failures into a JSON-RPC error response.
+    pub(crate) async fn respond(&self, request: JsonRpcRequest) -> JsonRpcResponse {
+        let id = request.id;
+        self.handle_request(request).await.unwrap_or_else(|err| {
+            rpc_response(id, Err(rpc_error(INTERNAL_ERROR, format!("{:?}", err))))
+        })
+    }
+
+    fn handle_resources_subscription(&self, request: &JsonRpcRequest) -> Result<Value, ErrorData> {
+        let Some(subscriptions) = &self.subscriptions else {
+            return Err(rpc_error(
+                METHOD_NOT_FOUND,
+                format!("{} is not supported in this session", request.method),
+            ));
+        };
+        let uri = request
+            .params
+            .as_ref()
+            .and_then(|params| params.get("uri"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| rpc_error(INVALID_PARAMS, "Missing resource URI".to_string()))?;
+
+        if request.method == "resources/unsubscribe" {
+            subscriptions.unsubscribe(uri);
+        } else {
+            subscriptions
+                .subscribe(self.client.clone(), uri, self.hevy_router.poll_interval)
+                .map_err(|message| rpc_error(INVALID_PARAMS, message))?;
         }
+        Ok(json!({}))
+    }
+
+    async fn handle_prompts_get(&self, params: Option<Value>) -> Result<Value, ErrorData> {
+        let params = params.unwrap_or_default();
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| rpc_error(INVALID_PARAMS, "Missing prompt name".to_string()))?;
+        let no_arguments = Map::new();
+        let arguments = match params.get("arguments") {
+            None | Some(Value::Null) => &no_arguments,
+            Some(Value::Object(arguments)) => arguments,
+            Some(_) => {
+                return Err(rpc_error(
+                    INVALID_PARAMS,
+                    "Prompt arguments must be an object".to_string(),
+                ));
+            }
+        };
+
+        let client = self.client.client();
+        let result = get_prompt(&client, name, arguments)
+            .await
+            .map_err(|err| match err {
+                PromptError::NotFound(message) | PromptError::InvalidParameters(message) => {
+                    rpc_error(INVALID_PARAMS, message)
+                }
+                PromptError::InternalError(message) => rpc_error(INTERNAL_ERROR, message),
+            })?;
+        serde_json::to_value(result).map_err(|err| rpc_error(INTERNAL_ERROR, err.to_string()))
+    }
+
+    async fn handle_resources_read(&self, params: Option<Value>) -> Result<Value, ErrorData> {
+        let uri = params
+            .as_ref()
+            .and_then(|params| params.get("uri"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| rpc_error(INVALID_PARAMS, "Missing resource URI".to_string()))?;
+
+        let client = self.client.client();
+        let text = read_resource(&client, uri).await.map_err(|err| match err {
+            ResourceError::NotFound(message) => rpc_error(RESOURCE_NOT_FOUND, message),
+            ResourceError::ExecutionError(message) => rpc_error(INTERNAL_ERROR, message),
+        })?;
+
+        Ok(json!({
+            "contents": [{
+                "uri": uri,
+                "mimeType": JSON_MIME_TYPE,
+                "text": text
+            }]
+        }))
     }
 }
 
@@ -70,11 +449,18 @@ impl Router for RequestRouter {
     }
 
     fn capabilities(&self) -> ServerCapabilities {
-        CapabilitiesBuilder::new().with_tools(false).build()
+        CapabilitiesBuilder::new()
+            .with_tools(false)
+            .with_resources(self.subscriptions.is_some(), false)
+            .with_prompts(false)
+            .build()
     }
 
     fn list_tools(&self) -> Vec<Tool> {
-        list_tools()
+        self.list_annotated_tools()
+            .into_iter()
+            .map(|tool| tool.tool)
+            .collect()
     }
 
     fn call_tool(
@@ -82,29 +468,136 @@ impl Router for RequestRouter {
         tool_name: &str,
         arguments: Value,
     ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
-        let client = self.hevy_router.get_or_create_client(&self.api_key);
-        call_tool(client, tool_name, arguments)
+        let tool = match self.hevy_router.tools.get(tool_name) {
+            Some(tool) => tool,
+            None => {
+                let message = format!("Unknown tool: {}", tool_name);
+                return Box::pin(async move { Err(ToolError::NotFound(message)) });
+            }
+        };
+        if !self.is_tool_enabled(tool) {
+            let message = format!("{} is not available in this session", tool_name);
+            return Box::pin(async move { Err(ToolError::NotFound(message)) });
+        }
+
+        let client = self.client.client();
+        if self.hevy_router.requires_confirmation(tool_name) {
+            // A tool swapped in after the confirmation list was checked could
+            // write during the preview, so it is refused instead.
+            if !tool.supports_dry_run() {
+                let message = format!(
+                    "{} requires confirmation but does not support dry_run",
+                    tool_name
+                );
+                return Box::pin(async move { Err(ToolError::ExecutionError(message)) });
+            }
+            return call_tool_with_confirmation(
+                tool.handler.clone(),
+                client,
+                self.confirmations.clone(),
+                tool_name,
+                arguments,
+            );
+        }
+        call_handler(&tool.handler, client, arguments)
     }
 
     fn list_resources(&self) -> Vec<Resource> {
-        vec![]
+        list_resources()
     }
 
     fn read_resource(
         &self,
-        _uri: &str,
+        uri: &str,
     ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
-        Box::pin(async { Err(ResourceError::NotFound("No resources available".into())) })
+        let client = self.client.client();
+        let uri = uri.to_string();
+        Box::pin(async move { read_resource(&client, &uri).await })
     }
 
     fn list_prompts(&self) -> Vec<Prompt> {
-        vec![]
+        list_prompts()
     }
 
     fn get_prompt(
         &self,
-        _prompt_name: &str,
+        prompt_name: &str,
     ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
-        Box::pin(async { Err(PromptError::NotFound("No prompts available".into())) })
+        // Only reached without arguments; `handle_request` serves prompts/get itself.
+        let client = self.client.client();
+        let prompt_name = prompt_name.to_string();
+        Box::pin(async move {
+            let result = get_prompt(&client, &prompt_name, &Map::new()).await?;
+            Ok(result
+                .messages
+                .into_iter()
+                .filter_map(|message| match message.content {
+                    PromptMessageContent::Text { text } => Some(text),
+                    _ => None,
+                })
+                .collect())
+        })
+    }
+}
+
+pub(crate) fn progress_token(request: &JsonRpcRequest) -> Option<Value> {
+    request
+        .params
+        .as_ref()
+        .and_then(|params| params.get("_meta"))
+        .and_then(|meta| meta.get("progressToken"))
+        .cloned()
+}
+
+/// Mirrors a tool's JSON text output as `structuredContent`.
+fn add_structured_content(response: &mut JsonRpcResponse) {
+    let Some(result) = response.result.as_mut() else {
+        return;
+    };
+    if result.get("isError").and_then(Value::as_bool) == Some(true) {
+        return;
+    }
+    let structured = result
+        .get("content")
+        .and_then(|content| content.get(0))
+        .and_then(|content| content.get("text"))
+        .and_then(Value::as_str)
+        .and_then(|text| serde_json::from_str::<Value>(text).ok());
+    match structured {
+        Some(structured) => result["structuredContent"] = structured,
+        None => warn!("Tool with an output schema returned text that is not JSON"),
+    }
+}
+
+fn progress_notification(token: &Value, progress: u32) -> JsonRpcNotification {
+    JsonRpcNotification {
+        jsonrpc: "2.0".to_string(),
+        method: "notifications/progress".to_string(),
+        params: Some(json!({
+            "progressToken": token,
+            "progress": progress,
+            "total": 1
+        })),
+    }
+}
+
+fn rpc_response(id: Option<u64>, result: Result<Value, ErrorData>) -> JsonRpcResponse {
+    let (result, error) = match result {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result,
+        error,
+    }
+}
+
+fn rpc_error(code: i32, message: String) -> ErrorData {
+    ErrorData {
+        code,
+        message,
+        data: None,
     }
 }
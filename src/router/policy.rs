@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::router::registry::{ToolDefinition, ToolRegistry};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ToolPolicy {
+    read_only: bool,
+    allow: Option<HashSet<String>>,
+    deny: HashSet<String>,
+}
+
+impl ToolPolicy {
+    pub fn new(
+        registry: &ToolRegistry,
+        read_only: bool,
+        allow_tools: Option<&str>,
+        deny_tools: Option<&str>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            read_only,
+            allow: allow_tools
+                .map(|list| parse_tool_list(registry, list, "allow_tools"))
+                .transpose()?,
+            deny: deny_tools
+                .map(|list| parse_tool_list(registry, list, "deny_tools"))
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn permits(&self, tool: &ToolDefinition) -> bool {
+        if self.read_only && !tool.is_read_only() {
+            return false;
+        }
+        if let Some(allow) = &self.allow
+            && !allow.contains(tool.name())
+        {
+            return false;
+        }
+        !self.deny.contains(tool.name())
+    }
+}
+
+fn parse_tool_list(
+    registry: &ToolRegistry,
+    list: &str,
+    field: &str,
+) -> Result<HashSet<String>, String> {
+    let mut tools = HashSet::new();
+    for name in list
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        if registry.get(name).is_none() {
+            return Err(format!("Unknown tool in {}: {}", field, name));
+        }
+        tools.insert(name.to_string());
+    }
+    Ok(tools)
+}
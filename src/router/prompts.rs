@@ -0,0 +1,471 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use mcp_spec::handler::PromptError;
+use mcp_spec::prompt::{Prompt, PromptArgument, PromptMessage, PromptMessageRole};
+use mcp_spec::protocol::GetPromptResult;
+use serde_json::{Map, Value};
+
+use crate::hevy::client::HevyError;
+use crate::hevy::{ExerciseHistoryEntry, ExerciseTemplate, HevyClient, Workout};
+use crate::router::formatters::{format_exercise_template, format_routine, format_workout};
+
+const WORKOUT_PAGE_SIZE: u32 = 10;
+const MAX_WORKOUT_PAGES: u32 = 10;
+const RECENT_WORKOUTS: u32 = 5;
+const RECENT_SESSIONS: usize = 3;
+const PLAN_HISTORY_WEEKS: u32 = 12;
+
+#[derive(Clone, Copy)]
+enum PromptKind {
+    WeeklyTrainingReview,
+    PlanNextSession,
+    LogWorkoutFromNotes,
+    DiagnoseStalledLift,
+}
+
+const PROMPT_KINDS: [PromptKind; 4] = [
+    PromptKind::WeeklyTrainingReview,
+    PromptKind::PlanNextSession,
+    PromptKind::LogWorkoutFromNotes,
+    PromptKind::DiagnoseStalledLift,
+];
+
+impl PromptKind {
+    fn name(self) -> &'static str {
+        match self {
+            PromptKind::WeeklyTrainingReview => "weekly_training_review",
+            PromptKind::PlanNextSession => "plan_next_session",
+            PromptKind::LogWorkoutFromNotes => "log_workout_from_notes",
+            PromptKind::DiagnoseStalledLift => "diagnose_stalled_lift",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            PromptKind::WeeklyTrainingReview => {
+                "Review recent training: consistency, volume and suggestions for next week"
+            }
+            PromptKind::PlanNextSession => {
+                "Plan the next session of a routine from recent performance on its exercises"
+            }
+            PromptKind::LogWorkoutFromNotes => {
+                "Turn free-form workout notes into a logged Hevy workout"
+            }
+            PromptKind::DiagnoseStalledLift => {
+                "Find out why an exercise has stopped progressing and how to fix it"
+            }
+        }
+    }
+
+    fn arguments(self) -> Vec<PromptArgument> {
+        match self {
+            PromptKind::WeeklyTrainingReview => vec![argument(
+                "weeks",
+                "Number of weeks to review (default 1)",
+                false,
+            )],
+            PromptKind::PlanNextSession => {
+                vec![argument("routine_id", "ID of the routine to plan", true)]
+            }
+            PromptKind::LogWorkoutFromNotes => vec![
+                argument("notes", "Free-form notes describing the workout", true),
+                argument(
+                    "date",
+                    "Date of the workout (YYYY-MM-DD, default today)",
+                    false,
+                ),
+            ],
+            PromptKind::DiagnoseStalledLift => vec![
+                argument(
+                    "exercise_template_id",
+                    "Exercise template ID of the stalled lift",
+                    true,
+                ),
+                argument(
+                    "weeks",
+                    "Number of weeks of history to analyse (default 12)",
+                    false,
+                ),
+            ],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        PROMPT_KINDS.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+fn argument(name: &str, description: &str, required: bool) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        required: Some(required),
+    }
+}
+
+pub(crate) fn list_prompts() -> Vec<Prompt> {
+    PROMPT_KINDS
+        .into_iter()
+        .map(|kind| {
+            Prompt::new(
+                kind.name(),
+                Some(kind.description()),
+                Some(kind.arguments()),
+            )
+        })
+        .collect()
+}
+
+pub(crate) async fn get_prompt(
+    client: &HevyClient,
+    name: &str,
+    arguments: &Map<String, Value>,
+) -> Result<GetPromptResult, PromptError> {
+    let kind = PromptKind::from_name(name)
+        .ok_or_else(|| PromptError::NotFound(format!("Unknown prompt: {}", name)))?;
+    let text = render_prompt(client, kind, arguments).await?;
+    Ok(GetPromptResult {
+        description: Some(kind.description().to_string()),
+        messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+    })
+}
+
+async fn render_prompt(
+    client: &HevyClient,
+    kind: PromptKind,
+    arguments: &Map<String, Value>,
+) -> Result<String, PromptError> {
+    match kind {
+        PromptKind::WeeklyTrainingReview => {
+            let weeks = weeks_arg(arguments, 1)?;
+            weekly_training_review(client, weeks).await
+        }
+        PromptKind::PlanNextSession => {
+            let routine_id = required_arg(arguments, "routine_id")?;
+            plan_next_session(client, routine_id).await
+        }
+        PromptKind::LogWorkoutFromNotes => {
+            let notes = required_arg(arguments, "notes")?;
+            let date = optional_arg(arguments, "date");
+            log_workout_from_notes(client, notes, date).await
+        }
+        PromptKind::DiagnoseStalledLift => {
+            let exercise_template_id = required_arg(arguments, "exercise_template_id")?;
+            let weeks = weeks_arg(arguments, PLAN_HISTORY_WEEKS)?;
+            diagnose_stalled_lift(client, exercise_template_id, weeks).await
+        }
+    }
+}
+
+fn optional_arg<'a>(arguments: &'a Map<String, Value>, name: &str) -> Option<&'a str> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn required_arg<'a>(arguments: &'a Map<String, Value>, name: &str) -> Result<&'a str, PromptError> {
+    optional_arg(arguments, name).ok_or_else(|| {
+        PromptError::InvalidParameters(format!("Missing required argument: {}", name))
+    })
+}
+
+fn weeks_arg(arguments: &Map<String, Value>, default: u32) -> Result<u32, PromptError> {
+    let Some(value) = optional_arg(arguments, "weeks") else {
+        return Ok(default);
+    };
+    value
+        .parse()
+        .ok()
+        .filter(|weeks| (1..=52).contains(weeks))
+        .ok_or_else(|| {
+            PromptError::InvalidParameters(format!(
+                "weeks must be a whole number between 1 and 52, got {}",
+                value
+            ))
+        })
+}
+
+fn fetch_error(context: &str) -> impl FnOnce(HevyError) -> PromptError + '_ {
+    move |err| match err {
+        HevyError::NotFound(_) => PromptError::InvalidParameters(format!("{} not found", context)),
+        err => PromptError::InternalError(format!("Error fetching {}: {}", context, err)),
+    }
+}
+
+async fn weekly_training_review(client: &HevyClient, weeks: u32) -> Result<String, PromptError> {
+    let since = weeks_ago(weeks);
+    let workouts = workouts_since(client, since)
+        .await
+        .map_err(fetch_error("workouts"))?;
+    let templates = exercise_templates(client, workout_template_ids(&workouts)).await;
+
+    let mut output = format!(
+        "Review my training over the last {} week(s). Summarise what I did, how consistent I \
+        was, how volume was spread across muscle groups and exercises, and any standout or \
+        missed sessions. Finish with concrete suggestions for next week.\n\n\
+        Workouts since {} ({}):\n\n",
+        weeks,
+        format_date(since),
+        workouts.len()
+    );
+    if workouts.is_empty() {
+        output.push_str("No workouts were logged in this period.\n");
+    }
+    for workout in &workouts {
+        output.push_str(&format_workout(workout));
+        output.push_str("---\n");
+    }
+    output.push_str(&format_template_legend(&templates));
+    Ok(output)
+}
+
+async fn plan_next_session(client: &HevyClient, routine_id: &str) -> Result<String, PromptError> {
+    let routine = client
+        .get_routine(routine_id)
+        .await
+        .map_err(fetch_error("routine"))?;
+
+    let since = weeks_ago(PLAN_HISTORY_WEEKS);
+    let template_ids: BTreeSet<&str> = routine
+        .exercises
+        .iter()
+        .map(|exercise| exercise.exercise_template_id.as_str())
+        .collect();
+    let templates = exercise_templates(client, template_ids.iter().copied()).await;
+
+    let mut output = format!(
+        "Plan my next session of the routine \"{}\". For every exercise, suggest the weight and \
+        reps for each set based on my recent performance, progressing where the last sessions \
+        were completed comfortably and holding or backing off where they were not. Briefly \
+        explain each choice.\n\n{}\nRecent sessions (last {} per exercise):\n",
+        routine.title,
+        format_routine(&routine),
+        RECENT_SESSIONS
+    );
+    for id in template_ids {
+        let history = client
+            .get_exercise_history(id, Some(format_timestamp(since)), None)
+            .await
+            .map_err(fetch_error("exercise history"))?;
+        output.push_str(&format!("\n{}\n", template_title(&templates, id)));
+        output.push_str(&format_sessions(
+            &history.exercise_history,
+            Some(RECENT_SESSIONS),
+        ));
+    }
+    output.push('\n');
+    output.push_str(&format_template_legend(&templates));
+    Ok(output)
+}
+
+async fn log_workout_from_notes(
+    client: &HevyClient,
+    notes: &str,
+    date: Option<&str>,
+) -> Result<String, PromptError> {
+    let recent = client
+        .get_workouts(Some(1), Some(RECENT_WORKOUTS))
+        .await
+        .map_err(fetch_error("workouts"))?
+        .data
+        .workouts;
+    let templates = exercise_templates(client, workout_template_ids(&recent)).await;
+    let date = date.map_or_else(|| format_date(Utc::now()), str::to_string);
+
+    let mut output = format!(
+        "Log a workout in Hevy from my notes below. Match every exercise to an exercise template \
+        ID, preferring the templates I have used recently (listed below) and searching \
+        get_exercise_templates for anything else. Call create_workout with dry_run set to true \
+        first, show me what would be logged, and only log it once I confirm.\n\n\
+        Workout date: {}\n\nMy notes:\n{}\n\nMy last {} workouts:\n\n",
+        date,
+        notes,
+        recent.len()
+    );
+    for workout in &recent {
+        output.push_str(&format_workout(workout));
+        output.push_str("---\n");
+    }
+    output.push_str(&format_template_legend(&templates));
+    Ok(output)
+}
+
+async fn diagnose_stalled_lift(
+    client: &HevyClient,
+    exercise_template_id: &str,
+    weeks: u32,
+) -> Result<String, PromptError> {
+    let template = client
+        .get_exercise_template(exercise_template_id)
+        .await
+        .map_err(fetch_error("exercise template"))?;
+    let since = weeks_ago(weeks);
+    let history = client
+        .get_exercise_history(exercise_template_id, Some(format_timestamp(since)), None)
+        .await
+        .map_err(fetch_error("exercise history"))?;
+
+    Ok(format!(
+        "My {} has stalled. Using my history below, work out when progress stopped and what is \
+        most likely holding it back: intensity, volume, frequency, rep ranges, RPE trends or \
+        recovery. Then recommend a specific plan for the next 4-6 weeks to get it moving \
+        again.\n\n{}\nSessions over the last {} weeks:\n{}",
+        template.title,
+        format_exercise_template(&template),
+        weeks,
+        format_sessions(&history.exercise_history, None)
+    ))
+}
+
+fn weeks_ago(weeks: u32) -> DateTime<Utc> {
+    Utc::now() - TimeDelta::weeks(weeks.into())
+}
+
+fn format_date(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d").to_string()
+}
+
+fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+async fn workouts_since(
+    client: &HevyClient,
+    since: DateTime<Utc>,
+) -> Result<Vec<Workout>, HevyError> {
+    let mut workouts = Vec::new();
+    for page in 1..=MAX_WORKOUT_PAGES {
+        let response = client
+            .get_workouts(Some(page), Some(WORKOUT_PAGE_SIZE))
+            .await?;
+        // Workouts come back newest first, so the first older one ends the scan.
+        let reached_start = response
+            .data
+            .workouts
+            .iter()
+            .any(|workout| workout.start_time < since);
+        workouts.extend(
+            response
+                .data
+                .workouts
+                .into_iter()
+                .filter(|workout| workout.start_time >= since),
+        );
+        if reached_start || page >= response.page_count {
+            break;
+        }
+    }
+    Ok(workouts)
+}
+
+fn workout_template_ids(workouts: &[Workout]) -> impl Iterator<Item = &str> {
+    workouts
+        .iter()
+        .flat_map(|workout| &workout.exercises)
+        .map(|exercise| exercise.exercise_template_id.as_str())
+}
+
+async fn exercise_templates<'a>(
+    client: &HevyClient,
+    ids: impl IntoIterator<Item = &'a str>,
+) -> BTreeMap<String, ExerciseTemplate> {
+    let mut templates = BTreeMap::new();
+    for id in ids.into_iter().collect::<BTreeSet<_>>() {
+        // A missing template only costs the prompt a title, so failures are skipped.
+        if let Ok(template) = client.get_exercise_template(id).await {
+            templates.insert(id.to_string(), template);
+        }
+    }
+    templates
+}
+
+fn template_title(templates: &BTreeMap<String, ExerciseTemplate>, id: &str) -> String {
+    match templates.get(id) {
+        Some(template) => format!("{} ({})", template.title, id),
+        None => format!("Exercise Template ID: {}", id),
+    }
+}
+
+fn format_template_legend(templates: &BTreeMap<String, ExerciseTemplate>) -> String {
+    if templates.is_empty() {
+        return String::new();
+    }
+    let mut output = String::from("\nExercise templates:\n");
+    for (id, template) in templates {
+        output.push_str(&format!(
+            "  {}: {} ({:?})\n",
+            id, template.title, template.primary_muscle_group
+        ));
+    }
+    output
+}
+
+fn format_sessions(entries: &[ExerciseHistoryEntry], limit: Option<usize>) -> String {
+    let mut sessions: Vec<Vec<&ExerciseHistoryEntry>> = Vec::new();
+    let mut sorted: Vec<&ExerciseHistoryEntry> = entries.iter().collect();
+    sorted.sort_by_key(|entry| entry.workout_start_time);
+    for entry in sorted {
+        match sessions.last_mut() {
+            Some(session) if session[0].workout_id == entry.workout_id => session.push(entry),
+            _ => sessions.push(vec![entry]),
+        }
+    }
+
+    if sessions.is_empty() {
+        return "  No sessions logged.\n".to_string();
+    }
+    let skip = limit.map_or(0, |limit| sessions.len().saturating_sub(limit));
+
+    let mut output = String::new();
+    for session in &sessions[skip..] {
+        let sets: Vec<String> = session
+            .iter()
+            .map(|entry| format_history_set(entry))
+            .collect();
+        output.push_str(&format!(
+            "  {} {}: {}",
+            format_date(session[0].workout_start_time),
+            session[0].workout_title,
+            sets.join(", ")
+        ));
+        let best = session
+            .iter()
+            .filter_map(|entry| estimated_one_rep_max(entry))
+            .reduce(f64::max);
+        if let Some(best) = best {
+            output.push_str(&format!(" (est. 1RM {:.1}kg)", best));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn format_history_set(entry: &ExerciseHistoryEntry) -> String {
+    let mut set = String::new();
+    if let Some(w) = entry.weight_kg {
+        set.push_str(&format!("{:.1}kg ", w));
+    }
+    if let Some(r) = entry.reps {
+        set.push_str(&format!("x{} ", r));
+    }
+    if let Some(d) = entry.duration_seconds {
+        set.push_str(&format!("{}s ", d));
+    }
+    if let Some(m) = entry.distance_meters {
+        set.push_str(&format!("{:.0}m ", m));
+    }
+    if let Some(rpe) = entry.rpe {
+        set.push_str(&format!("@RPE {} ", rpe));
+    }
+    set.trim_end().to_string()
+}
+
+/// Epley estimate, which is only meaningful for sets of roughly 1-12 reps.
+fn estimated_one_rep_max(entry: &ExerciseHistoryEntry) -> Option<f64> {
+    let weight = entry.weight_kg.filter(|weight| *weight > 0.0)?;
+    let reps = entry.reps.filter(|reps| (1..=12).contains(reps))?;
+    Some(weight * (1.0 + f64::from(reps) / 30.0))
+}
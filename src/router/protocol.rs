@@ -0,0 +1,62 @@
+use mcp_spec::protocol::JsonRpcRequest;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// MCP specification revisions the server can speak, oldest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ProtocolVersion {
+    #[serde(rename = "2024-11-05")]
+    V2024_11_05,
+    #[serde(rename = "2025-03-26")]
+    V2025_03_26,
+    #[serde(rename = "2025-06-18")]
+    V2025_06_18,
+}
+
+const PROTOCOL_VERSIONS: [ProtocolVersion; 3] = [
+    ProtocolVersion::V2024_11_05,
+    ProtocolVersion::V2025_03_26,
+    ProtocolVersion::V2025_06_18,
+];
+
+impl ProtocolVersion {
+    pub const LATEST: Self = ProtocolVersion::V2025_06_18;
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProtocolVersion::V2024_11_05 => "2024-11-05",
+            ProtocolVersion::V2025_03_26 => "2025-03-26",
+            ProtocolVersion::V2025_06_18 => "2025-06-18",
+        }
+    }
+
+    pub fn parse(version: &str) -> Option<Self> {
+        PROTOCOL_VERSIONS
+            .into_iter()
+            .find(|supported| supported.as_str() == version)
+    }
+
+    /// Picks the version an `initialize` request asked for, or the latest one
+    /// when the client asked for something the server does not support.
+    pub fn negotiate(request: &JsonRpcRequest) -> Self {
+        request
+            .params
+            .as_ref()
+            .and_then(|params| params.get("protocolVersion"))
+            .and_then(Value::as_str)
+            .and_then(Self::parse)
+            .unwrap_or(Self::LATEST)
+    }
+
+    pub fn has_tool_annotations(self) -> bool {
+        self >= ProtocolVersion::V2025_03_26
+    }
+
+    pub fn has_tool_titles(self) -> bool {
+        self >= ProtocolVersion::V2025_06_18
+    }
+
+    pub fn has_structured_output(self) -> bool {
+        self >= ProtocolVersion::V2025_06_18
+    }
+}
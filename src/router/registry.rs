@@ -0,0 +1,168 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use mcp_spec::handler::ToolError;
+use mcp_spec::{Content, Tool};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::hevy::HevyClient;
+use crate::router::tools::builtin_tools;
+
+pub type ToolFuture = Pin<Box<dyn Future<Output = Result<String, ToolError>> + Send + 'static>>;
+pub type ToolHandler = Arc<dyn Fn(Arc<HevyClient>, Value) -> ToolFuture + Send + Sync>;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolAnnotations {
+    pub title: String,
+    pub read_only_hint: bool,
+    pub destructive_hint: bool,
+    pub idempotent_hint: bool,
+    pub open_world_hint: bool,
+}
+
+impl ToolAnnotations {
+    pub fn read_only(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: true,
+        }
+    }
+
+    pub fn write(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: true,
+        }
+    }
+
+    pub fn destructive(mut self) -> Self {
+        self.destructive_hint = true;
+        self
+    }
+
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent_hint = true;
+        self
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolDefinition {
+    #[serde(flatten)]
+    pub(crate) tool: Tool,
+    pub(crate) title: String,
+    pub(crate) annotations: ToolAnnotations,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) output_schema: Option<Value>,
+    #[serde(skip)]
+    pub(crate) supports_dry_run: bool,
+    #[serde(skip)]
+    pub(crate) handler: ToolHandler,
+}
+
+impl ToolDefinition {
+    pub fn new<F, Fut>(
+        annotations: ToolAnnotations,
+        name: &str,
+        description: &str,
+        input_schema: Value,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(Arc<HevyClient>, Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, ToolError>> + Send + 'static,
+    {
+        Self {
+            tool: Tool::new(name, description, input_schema),
+            title: annotations.title.clone(),
+            annotations,
+            output_schema: None,
+            supports_dry_run: false,
+            handler: Arc::new(move |client, arguments| Box::pin(handler(client, arguments))),
+        }
+    }
+
+    /// Declares the JSON shape of the tool's output. The handler must then return
+    /// JSON text matching it, which sessions on 2025-06-18 or later also receive
+    /// as `structuredContent`.
+    pub fn with_output_schema(mut self, output_schema: Value) -> Self {
+        self.output_schema = Some(output_schema);
+        self
+    }
+
+    /// Declares that the handler honours `dry_run: true` by returning a preview
+    /// without writing anything. Only such tools can require confirmation.
+    pub fn with_dry_run(mut self) -> Self {
+        self.supports_dry_run = true;
+        self
+    }
+
+    pub fn supports_dry_run(&self) -> bool {
+        self.supports_dry_run
+    }
+
+    pub fn name(&self) -> &str {
+        &self.tool.name
+    }
+
+    pub fn annotations(&self) -> &ToolAnnotations {
+        &self.annotations
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.annotations.read_only_hint
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<ToolDefinition>,
+}
+
+impl ToolRegistry {
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        for tool in builtin_tools() {
+            registry.register(tool);
+        }
+        registry
+    }
+
+    pub fn register(&mut self, tool: ToolDefinition) {
+        match self
+            .tools
+            .iter_mut()
+            .find(|existing| existing.name() == tool.name())
+        {
+            Some(existing) => *existing = tool,
+            None => self.tools.push(tool),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ToolDefinition> {
+        self.tools.iter().find(|tool| tool.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ToolDefinition> {
+        self.tools.iter()
+    }
+}
+
+pub(crate) fn call_handler(
+    handler: &ToolHandler,
+    client: Arc<HevyClient>,
+    arguments: Value,
+) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+    let future = handler(client, arguments);
+    Box::pin(async move { future.await.map(|text| vec![Content::text(text)]) })
+}
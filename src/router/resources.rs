@@ -0,0 +1,156 @@
+use mcp_spec::Resource;
+use mcp_spec::handler::ResourceError;
+use serde::Serialize;
+
+use crate::hevy::HevyClient;
+use crate::hevy::client::HevyError;
+
+pub(crate) const SCHEME: &str = "hevy://";
+pub(crate) const JSON_MIME_TYPE: &str = "application/json";
+
+#[derive(Clone, Copy)]
+enum ResourceKind {
+    Workouts,
+    Routines,
+    ExerciseTemplates,
+    RoutineFolders,
+}
+
+const RESOURCE_KINDS: [ResourceKind; 4] = [
+    ResourceKind::Workouts,
+    ResourceKind::Routines,
+    ResourceKind::ExerciseTemplates,
+    ResourceKind::RoutineFolders,
+];
+
+impl ResourceKind {
+    fn path(self) -> &'static str {
+        match self {
+            ResourceKind::Workouts => "workouts",
+            ResourceKind::Routines => "routines",
+            ResourceKind::ExerciseTemplates => "exercise_templates",
+            ResourceKind::RoutineFolders => "routine_folders",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ResourceKind::Workouts => "workout",
+            ResourceKind::Routines => "routine",
+            ResourceKind::ExerciseTemplates => "exercise template",
+            ResourceKind::RoutineFolders => "routine folder",
+        }
+    }
+
+    fn from_path(path: &str) -> Option<Self> {
+        RESOURCE_KINDS.into_iter().find(|kind| kind.path() == path)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ResourceTemplate {
+    uri_template: String,
+    name: String,
+    description: String,
+    mime_type: &'static str,
+}
+
+pub(crate) fn list_resources() -> Vec<Resource> {
+    RESOURCE_KINDS
+        .into_iter()
+        .map(|kind| Resource {
+            uri: format!("{}{}", SCHEME, kind.path()),
+            name: kind.path().to_string(),
+            description: Some(format!(
+                "Paginated list of {}s as JSON. Append ?page=N to read past the first page.",
+                kind.label()
+            )),
+            mime_type: JSON_MIME_TYPE.to_string(),
+            annotations: None,
+        })
+        .collect()
+}
+
+pub(crate) fn list_resource_templates() -> Vec<ResourceTemplate> {
+    RESOURCE_KINDS
+        .into_iter()
+        .map(|kind| ResourceTemplate {
+            uri_template: format!("{}{}/{{id}}", SCHEME, kind.path()),
+            name: kind.label().to_string(),
+            description: format!("A single {} by ID as JSON", kind.label()),
+            mime_type: JSON_MIME_TYPE,
+        })
+        .collect()
+}
+
+pub(crate) async fn read_resource(client: &HevyClient, uri: &str) -> Result<String, ResourceError> {
+    let not_found = || ResourceError::NotFound(format!("Unknown resource: {}", uri));
+
+    let rest = uri.strip_prefix(SCHEME).ok_or_else(not_found)?;
+    let (path, query) = match rest.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (rest, None),
+    };
+    let (kind, id) = match path.split_once('/') {
+        Some((kind, id)) if !id.is_empty() && !id.contains('/') => (kind, Some(id)),
+        Some(_) => return Err(not_found()),
+        None => (path, None),
+    };
+    let kind = ResourceKind::from_path(kind).ok_or_else(not_found)?;
+
+    match id {
+        Some(id) => {
+            if query.is_some() {
+                return Err(not_found());
+            }
+            read_item(client, kind, id).await
+        }
+        None => {
+            let page = match query {
+                Some(query) => Some(parse_page(query).ok_or_else(not_found)?),
+                None => None,
+            };
+            read_collection(client, kind, page).await
+        }
+    }
+    .map_err(|err| match err {
+        HevyError::NotFound(_) => ResourceError::NotFound(format!("Resource not found: {}", uri)),
+        err => ResourceError::ExecutionError(err.to_string()),
+    })
+}
+
+fn parse_page(query: &str) -> Option<u32> {
+    query
+        .strip_prefix("page=")
+        .and_then(|page| page.parse().ok())
+        .filter(|page| *page >= 1)
+}
+
+async fn read_item(client: &HevyClient, kind: ResourceKind, id: &str) -> Result<String, HevyError> {
+    match kind {
+        ResourceKind::Workouts => to_json(&client.get_workout(id).await?),
+        ResourceKind::Routines => to_json(&client.get_routine(id).await?),
+        ResourceKind::ExerciseTemplates => to_json(&client.get_exercise_template(id).await?),
+        ResourceKind::RoutineFolders => to_json(&client.get_routine_folder(id).await?),
+    }
+}
+
+async fn read_collection(
+    client: &HevyClient,
+    kind: ResourceKind,
+    page: Option<u32>,
+) -> Result<String, HevyError> {
+    match kind {
+        ResourceKind::Workouts => to_json(&client.get_workouts(page, None).await?),
+        ResourceKind::Routines => to_json(&client.get_routines(page, None).await?),
+        ResourceKind::ExerciseTemplates => {
+            to_json(&client.get_exercise_templates(page, None).await?)
+        }
+        ResourceKind::RoutineFolders => to_json(&client.get_routine_folders(page, None).await?),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, HevyError> {
+    Ok(serde_json::to_string_pretty(value)?)
+}
@@ -0,0 +1,193 @@
+use std::collections::{BTreeSet, HashSet};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mcp_spec::protocol::JsonRpcNotification;
+use serde_json::json;
+use tokio::task::JoinHandle;
+use tokio::time::{MissedTickBehavior, interval};
+use tracing::warn;
+
+use crate::hevy::client::HevyError;
+use crate::hevy::{HevyClient, WorkoutEvent};
+use crate::router::resources::SCHEME;
+use crate::router::{ClientLease, NotificationSink};
+
+pub(crate) struct ResourceSubscriptions {
+    uris: Mutex<HashSet<String>>,
+    notify: NotificationSink,
+    poller: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl std::fmt::Debug for ResourceSubscriptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceSubscriptions")
+            .field("uris", &self.uris)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for ResourceSubscriptions {
+    fn drop(&mut self) {
+        if let Ok(mut poller) = self.poller.lock()
+            && let Some(handle) = poller.take()
+        {
+            handle.abort();
+        }
+    }
+}
+
+impl ResourceSubscriptions {
+    pub(crate) fn new(notify: NotificationSink) -> Self {
+        Self {
+            uris: Mutex::new(HashSet::new()),
+            notify,
+            poller: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn subscribe(
+        self: &Arc<Self>,
+        client: Arc<ClientLease>,
+        uri: &str,
+        poll_interval: Duration,
+    ) -> Result<(), String> {
+        if !is_workout_uri(uri) {
+            return Err(format!(
+                "Cannot subscribe to {}: only {}workouts and {}workouts/{{id}} support subscriptions",
+                uri, SCHEME, SCHEME
+            ));
+        }
+        self.uris.lock().unwrap().insert(uri.to_string());
+
+        let mut poller = self.poller.lock().unwrap();
+        if poller.as_ref().is_none_or(JoinHandle::is_finished) {
+            *poller = Some(tokio::spawn(poll_workout_events(
+                Arc::downgrade(self),
+                client,
+                poll_interval,
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn unsubscribe(&self, uri: &str) {
+        let mut uris = self.uris.lock().unwrap();
+        uris.remove(uri);
+        if uris.is_empty()
+            && let Some(handle) = self.poller.lock().unwrap().take()
+        {
+            handle.abort();
+        }
+    }
+
+    fn notify_changes(&self, workout_ids: &BTreeSet<String>) {
+        let collection_uri = format!("{}workouts", SCHEME);
+        let uris = self.uris.lock().unwrap();
+
+        let mut updated = Vec::new();
+        if uris.contains(&collection_uri) {
+            updated.push(collection_uri);
+        }
+        for id in workout_ids {
+            let uri = format!("{}workouts/{}", SCHEME, id);
+            if uris.contains(&uri) {
+                updated.push(uri);
+            }
+        }
+        drop(uris);
+
+        for uri in updated {
+            (self.notify)(JsonRpcNotification {
+                jsonrpc: "2.0".to_string(),
+                method: "notifications/resources/updated".to_string(),
+                params: Some(json!({ "uri": uri })),
+            });
+        }
+    }
+}
+
+fn is_workout_uri(uri: &str) -> bool {
+    match uri.strip_prefix(SCHEME) {
+        Some("workouts") => true,
+        Some(rest) => rest
+            .strip_prefix("workouts/")
+            .is_some_and(|id| !id.is_empty() && !id.contains(['/', '?'])),
+        None => false,
+    }
+}
+
+fn event_workout_id(event: &WorkoutEvent) -> Option<&str> {
+    event
+        .workout
+        .as_ref()
+        .map(|workout| workout.id.as_str())
+        .or(event.id.as_deref())
+}
+
+fn event_time(event: &WorkoutEvent) -> Option<DateTime<Utc>> {
+    event
+        .workout
+        .as_ref()
+        .and_then(|workout| workout.updated_at)
+        .or(event.deleted_at)
+}
+
+async fn poll_workout_events(
+    subscriptions: Weak<ResourceSubscriptions>,
+    client: Arc<ClientLease>,
+    poll_interval: Duration,
+) {
+    let mut since = Utc::now();
+    let mut ticker = interval(poll_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+
+        let (changed, latest) = match fetch_changes(&client.client(), since).await {
+            Ok(changes) => changes,
+            Err(err) => {
+                warn!("Polling workout events failed: {}", err);
+                continue;
+            }
+        };
+        since = latest;
+
+        let Some(subscriptions) = subscriptions.upgrade() else {
+            return;
+        };
+        if !changed.is_empty() {
+            subscriptions.notify_changes(&changed);
+        }
+    }
+}
+
+async fn fetch_changes(
+    client: &HevyClient,
+    since: DateTime<Utc>,
+) -> Result<(BTreeSet<String>, DateTime<Utc>), HevyError> {
+    let mut changed = BTreeSet::new();
+    let mut latest = since;
+    let mut page = 1;
+    loop {
+        let response = client
+            .get_workout_events(Some(page), Some(10), Some(since.to_rfc3339()))
+            .await?;
+        for event in &response.events {
+            if let Some(id) = event_workout_id(event) {
+                changed.insert(id.to_string());
+            }
+            // Step past the newest event so the next poll does not report it again.
+            if let Some(time) = event_time(event) {
+                latest = latest.max(time + TimeDelta::milliseconds(1));
+            }
+        }
+        if page >= response.page_count {
+            return Ok((changed, latest));
+        }
+        page += 1;
+    }
+}
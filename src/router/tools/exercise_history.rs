@@ -1,18 +1,14 @@
-use mcp_spec::Tool;
-use serde_json::json;
+use crate::router::args::ExerciseHistoryArgs;
+use crate::router::handlers::exercise_history::handle_get_exercise_history;
+use crate::router::registry::{ToolAnnotations, ToolDefinition};
+use crate::router::tools::input_schema;
 
-pub(crate) fn tools() -> Vec<Tool> {
-    vec![Tool::new(
+pub(crate) fn tools() -> Vec<ToolDefinition> {
+    vec![ToolDefinition::new(
+        ToolAnnotations::read_only("Get exercise history"),
         "get_exercise_history",
         "Get exercise history for a specific exercise template.",
-        json!({
-            "type": "object",
-            "properties": {
-                "exercise_template_id": {"type": "string", "description": "Exercise template ID"},
-                "start_date": {"type": "string", "description": "Optional ISO 8601 start date"},
-                "end_date": {"type": "string", "description": "Optional ISO 8601 end date"}
-            },
-            "required": ["exercise_template_id"]
-        }),
+        input_schema::<ExerciseHistoryArgs>(),
+        |client, args| async move { handle_get_exercise_history(&client, &args).await },
     )]
 }
@@ -1,4 +1,9 @@
-use mcp_spec::Tool;
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
+use serde_json::{Value, json};
+
+use crate::router::args::DryRunArgs;
+use crate::router::registry::ToolDefinition;
 
 mod exercise_history;
 mod exercise_templates;
@@ -6,7 +11,31 @@ mod routine_folders;
 mod routines;
 mod workouts;
 
-pub(crate) fn list_tools() -> Vec<Tool> {
+pub fn input_schema<T: JsonSchema>() -> Value {
+    let generator = SchemaSettings::draft2020_12()
+        .with(|settings| {
+            settings.inline_subschemas = true;
+            settings.meta_schema = None;
+        })
+        .into_generator();
+    let mut schema = generator.into_root_schema_for::<T>().to_value();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("title");
+        object.entry("properties").or_insert_with(|| json!({}));
+    }
+    schema
+}
+
+pub(crate) fn write_input_schema<T: JsonSchema>() -> Value {
+    let mut schema = input_schema::<T>();
+    let dry_run = input_schema::<DryRunArgs>()["properties"]["dry_run"].clone();
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.insert("dry_run".to_string(), dry_run);
+    }
+    schema
+}
+
+pub(crate) fn builtin_tools() -> Vec<ToolDefinition> {
     let mut tools = Vec::new();
     tools.extend(workouts::tools());
     tools.extend(routines::tools());
@@ -1,40 +1,33 @@
-use mcp_spec::Tool;
-use serde_json::json;
+use crate::router::args::{CreateRoutineFolderArgs, IdArgs, PaginationArgs};
+use crate::router::handlers::routine_folders::{
+    handle_create_routine_folder, handle_get_routine_folder, handle_get_routine_folders,
+};
+use crate::router::registry::{ToolAnnotations, ToolDefinition};
+use crate::router::tools::{input_schema, write_input_schema};
 
-pub(crate) fn tools() -> Vec<Tool> {
+pub(crate) fn tools() -> Vec<ToolDefinition> {
     vec![
-        Tool::new(
+        ToolDefinition::new(
+            ToolAnnotations::read_only("List routine folders"),
             "get_routine_folders",
             "List routine folders from Hevy. Folders help organize your workout routines.",
-            json!({
-                "type": "object",
-                "properties": {
-                    "page": {"type": "number", "description": "Page number (1-indexed)"},
-                    "page_size": {"type": "number", "description": "Number of items per page (max 10)"}
-                }
-            }),
+            input_schema::<PaginationArgs>(),
+            |client, args| async move { handle_get_routine_folders(&client, &args).await },
         ),
-        Tool::new(
+        ToolDefinition::new(
+            ToolAnnotations::read_only("Get routine folder"),
             "get_routine_folder",
             "Get a single routine folder by its ID.",
-            json!({
-                "type": "object",
-                "properties": {
-                    "id": {"type": "string", "description": "The routine folder ID"}
-                },
-                "required": ["id"]
-            }),
+            input_schema::<IdArgs>(),
+            |client, args| async move { handle_get_routine_folder(&client, &args).await },
         ),
-        Tool::new(
+        ToolDefinition::new(
+            ToolAnnotations::write("Create routine folder"),
             "create_routine_folder",
             "Create a new folder to organize workout routines in Hevy.",
-            json!({
-                "type": "object",
-                "properties": {
-                    "title": {"type": "string", "description": "Folder title"}
-                },
-                "required": ["title"]
-            }),
-        ),
+            write_input_schema::<CreateRoutineFolderArgs>(),
+            |client, args| async move { handle_create_routine_folder(&client, &args).await },
+        )
+        .with_dry_run(),
     ]
 }
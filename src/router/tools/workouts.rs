@@ -1,137 +1,80 @@
-use mcp_spec::Tool;
 use serde_json::json;
 
-pub(crate) fn tools() -> Vec<Tool> {
+use crate::router::args::{
+    CreateWorkoutArgs, IdArgs, LogWorkoutFromRoutineArgs, PaginationArgs, PatchWorkoutArgs,
+    UpdateWorkoutArgs, WorkoutEventsArgs,
+};
+use crate::router::handlers::workouts::{
+    handle_create_workout, handle_get_workout, handle_get_workout_events, handle_get_workouts,
+    handle_get_workouts_count, handle_log_workout_from_routine, handle_patch_workout,
+    handle_update_workout,
+};
+use crate::router::registry::{ToolAnnotations, ToolDefinition};
+use crate::router::tools::{input_schema, write_input_schema};
+
+pub(crate) fn tools() -> Vec<ToolDefinition> {
     vec![
-        Tool::new(
+        ToolDefinition::new(
+            ToolAnnotations::read_only("List workouts"),
             "get_workouts",
             "List workouts from Hevy with pagination. Returns workout summaries including title, date, and exercises.",
-            json!({
-                "type": "object",
-                "properties": {
-                    "page": {"type": "number", "description": "Page number (1-indexed)"},
-                    "page_size": {"type": "number", "description": "Number of items per page (max 10)"}
-                }
-            }),
+            input_schema::<PaginationArgs>(),
+            |client, args| async move { handle_get_workouts(&client, &args).await },
         ),
-        Tool::new(
+        ToolDefinition::new(
+            ToolAnnotations::read_only("Count workouts"),
             "get_workouts_count",
             "Get the total number of workouts on the account.",
             json!({"type": "object", "properties": {}}),
+            |client, _| async move { handle_get_workouts_count(&client).await },
         ),
-        Tool::new(
+        ToolDefinition::new(
+            ToolAnnotations::read_only("List workout events"),
             "get_workout_events",
             "List workout update and delete events since a given date, with pagination.",
-            json!({
-                "type": "object",
-                "properties": {
-                    "page": {"type": "number", "description": "Page number (1-indexed)"},
-                    "page_size": {"type": "number", "description": "Number of items per page (max 10)"},
-                    "since": {"type": "string", "description": "ISO 8601 timestamp to start from"}
-                }
-            }),
+            input_schema::<WorkoutEventsArgs>(),
+            |client, args| async move { handle_get_workout_events(&client, &args).await },
         ),
-        Tool::new(
+        ToolDefinition::new(
+            ToolAnnotations::read_only("Get workout"),
             "get_workout",
             "Get a single workout by its ID. Returns full workout details including all exercises and sets.",
-            json!({
-                "type": "object",
-                "properties": {
-                    "id": {"type": "string", "description": "The workout ID"}
-                },
-                "required": ["id"]
-            }),
+            input_schema::<IdArgs>(),
+            |client, args| async move { handle_get_workout(&client, &args).await },
         ),
-        Tool::new(
+        ToolDefinition::new(
+            ToolAnnotations::write("Create workout"),
             "create_workout",
-            "Create a new workout in Hevy. Requires title, start_time, end_time (ISO 8601), and exercises with sets.",
-            json!({
-                "type": "object",
-                "properties": {
-                    "title": {"type": "string", "description": "Workout title"},
-                    "is_private": {"type": "boolean", "description": "Whether the workout is private"},
-                    "start_time": {"type": "string", "description": "Start time in ISO 8601 format"},
-                    "end_time": {"type": "string", "description": "End time in ISO 8601 format"},
-                    "description": {"type": "string", "description": "Optional description"},
-                    "exercises": {
-                        "type": "array",
-                        "description": "Exercises in the workout",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "exercise_template_id": {"type": "string", "description": "Exercise template ID"},
-                                "superset_id": {"type": "number", "description": "Superset group ID"},
-                                "notes": {"type": "string", "description": "Exercise notes"},
-                                "sets": {
-                                    "type": "array",
-                                    "description": "Sets in the exercise",
-                                    "items": {
-                                        "type": "object",
-                                        "properties": {
-                                            "set_type": {"type": "string", "enum": ["normal", "warmup", "dropset", "failure"], "description": "Type of set"},
-                                            "weight_kg": {"type": "number", "description": "Weight in kg"},
-                                            "reps": {"type": "number", "description": "Number of reps"},
-                                            "duration_seconds": {"type": "number", "description": "Duration in seconds"},
-                                            "distance_meters": {"type": "number", "description": "Distance in meters"},
-                                            "rpe": {"type": "number", "description": "Rate of perceived exertion (1-10)"},
-                                            "custom_metric": {"type": "number", "description": "Custom metric value"}
-                                        },
-                                        "required": ["set_type"]
-                                    }
-                                }
-                            },
-                            "required": ["exercise_template_id"]
-                        }
-                    }
-                },
-                "required": ["title", "start_time", "end_time"]
-            }),
-        ),
-        Tool::new(
+            "Create a new workout in Hevy. Requires title and start_time (ISO 8601); end_time and exercises with sets are optional.",
+            write_input_schema::<CreateWorkoutArgs>(),
+            |client, args| async move { handle_create_workout(&client, &args).await },
+        )
+        .with_dry_run(),
+        ToolDefinition::new(
+            ToolAnnotations::write("Replace workout")
+                .destructive()
+                .idempotent(),
             "update_workout",
             "Update an existing workout by ID. Replaces all workout data with the provided values.",
-            json!({
-                "type": "object",
-                "properties": {
-                    "id": {"type": "string", "description": "The workout ID to update"},
-                    "title": {"type": "string", "description": "Workout title"},
-                    "is_private": {"type": "boolean", "description": "Whether the workout is private"},
-                    "start_time": {"type": "string", "description": "Start time in ISO 8601 format"},
-                    "end_time": {"type": "string", "description": "End time in ISO 8601 format"},
-                    "description": {"type": "string", "description": "Optional description"},
-                    "exercises": {
-                        "type": "array",
-                        "description": "Exercises in the workout",
-                        "items": {
-                            "type": "object",
-                            "properties": {
-                                "exercise_template_id": {"type": "string", "description": "Exercise template ID"},
-                                "superset_id": {"type": "number", "description": "Superset group ID"},
-                                "notes": {"type": "string", "description": "Exercise notes"},
-                                "sets": {
-                                    "type": "array",
-                                    "description": "Sets in the exercise",
-                                    "items": {
-                                        "type": "object",
-                                        "properties": {
-                                            "set_type": {"type": "string", "enum": ["normal", "warmup", "dropset", "failure"], "description": "Type of set"},
-                                            "weight_kg": {"type": "number", "description": "Weight in kg"},
-                                            "reps": {"type": "number", "description": "Number of reps"},
-                                            "duration_seconds": {"type": "number", "description": "Duration in seconds"},
-                                            "distance_meters": {"type": "number", "description": "Distance in meters"},
-                                            "rpe": {"type": "number", "description": "Rate of perceived exertion (1-10)"},
-                                            "custom_metric": {"type": "number", "description": "Custom metric value"}
-                                        },
-                                        "required": ["set_type"]
-                                    }
-                                }
-                            },
-                            "required": ["exercise_template_id"]
-                        }
-                    }
-                },
-                "required": ["id", "title", "start_time", "end_time"]
-            }),
-        ),
+            write_input_schema::<UpdateWorkoutArgs>(),
+            |client, args| async move { handle_update_workout(&client, &args).await },
+        )
+        .with_dry_run(),
+        ToolDefinition::new(
+            ToolAnnotations::write("Log workout from routine"),
+            "log_workout_from_routine",
+            "Log a workout that follows an existing routine. Copies the routine's exercises and sets (using the midpoint of any rep range) and links the workout to the routine. Use overrides to record the weight, reps or RPE actually performed.",
+            write_input_schema::<LogWorkoutFromRoutineArgs>(),
+            |client, args| async move { handle_log_workout_from_routine(&client, &args).await },
+        )
+        .with_dry_run(),
+        ToolDefinition::new(
+            ToolAnnotations::write("Patch workout").destructive(),
+            "patch_workout",
+            "Apply targeted edits to an existing workout without resending it. Fetches the current workout, applies the operations in order, saves the merged result and returns a before/after diff. Exercise and set indexes are 1-indexed as shown by get_workout.",
+            write_input_schema::<PatchWorkoutArgs>(),
+            |client, args| async move { handle_patch_workout(&client, &args).await },
+        )
+        .with_dry_run(),
     ]
 }
@@ -0,0 +1,588 @@
+use std::collections::{BTreeSet, HashMap};
+
+use mcp_spec::handler::ToolError::{self, ExecutionError, InvalidParameters};
+use tokio::task::JoinSet;
+
+use crate::hevy::client::HevyError;
+use crate::hevy::{
+    ExerciseType, HevyClient, RoutineExerciseInput, RoutineInput, RoutineSetInput, SetInput,
+    WorkoutExerciseInput, WorkoutInput,
+};
+
+/// The RPE values Hevy offers; it has no 6.5.
+const RPE_VALUES: [f64; 8] = [6.0, 7.0, 7.5, 8.0, 8.5, 9.0, 9.5, 10.0];
+
+#[derive(Default)]
+pub(crate) struct ValidationErrors(Vec<(String, String)>);
+
+impl ValidationErrors {
+    pub(crate) fn push(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.0.push((path.into(), message.into()));
+    }
+
+    pub(crate) fn into_result(self) -> Result<(), ToolError> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut message = format!("validation failed with {} problem(s):", self.0.len());
+        for (path, problem) in self.0 {
+            message.push_str(&format!("\n- {}: {}", path, problem));
+        }
+        Err(InvalidParameters(message))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Metric {
+    Weight,
+    Reps,
+    Duration,
+    Distance,
+}
+
+impl Metric {
+    fn field(self) -> &'static str {
+        match self {
+            Metric::Weight => "weight_kg",
+            Metric::Reps => "reps",
+            Metric::Duration => "duration_seconds",
+            Metric::Distance => "distance_meters",
+        }
+    }
+}
+
+fn expected_metrics(exercise_type: &ExerciseType) -> Option<&'static [Metric]> {
+    match exercise_type {
+        ExerciseType::WeightReps => Some(&[Metric::Weight, Metric::Reps]),
+        ExerciseType::Reps => Some(&[Metric::Reps]),
+        ExerciseType::Duration => Some(&[Metric::Duration]),
+        ExerciseType::WeightDuration => Some(&[Metric::Weight, Metric::Duration]),
+        ExerciseType::DistanceDuration => Some(&[Metric::Distance, Metric::Duration]),
+        ExerciseType::Weight => Some(&[Metric::Weight]),
+        ExerciseType::Unknown => None,
+    }
+}
+
+fn type_name(exercise_type: &ExerciseType) -> &'static str {
+    match exercise_type {
+        ExerciseType::WeightReps => "weight_reps",
+        ExerciseType::Reps => "reps",
+        ExerciseType::Duration => "duration",
+        ExerciseType::WeightDuration => "weight_duration",
+        ExerciseType::DistanceDuration => "distance_duration",
+        ExerciseType::Weight => "weight",
+        ExerciseType::Unknown => "unknown",
+    }
+}
+
+fn check_metrics(
+    errors: &mut ValidationErrors,
+    path: &str,
+    exercise_type: &ExerciseType,
+    present: &[Metric],
+    require_all: bool,
+) {
+    let Some(expected) = expected_metrics(exercise_type) else {
+        return;
+    };
+
+    for metric in present {
+        if !expected.contains(metric) {
+            errors.push(
+                format!("{}.{}", path, metric.field()),
+                format!("not used by {} exercises", type_name(exercise_type)),
+            );
+        }
+    }
+    if require_all {
+        for metric in expected {
+            if !present.contains(metric) {
+                errors.push(
+                    format!("{}.{}", path, metric.field()),
+                    format!("required for {} exercises", type_name(exercise_type)),
+                );
+            }
+        }
+    }
+}
+
+fn check_non_negative(errors: &mut ValidationErrors, path: &str, field: &str, value: Option<f64>) {
+    if let Some(value) = value
+        && value < 0.0
+    {
+        errors.push(format!("{}.{}", path, field), "must not be negative");
+    }
+}
+
+fn workout_set_metrics(set: &SetInput) -> Vec<Metric> {
+    let mut present = Vec::new();
+    if set.weight_kg.is_some() {
+        present.push(Metric::Weight);
+    }
+    if set.reps.is_some() {
+        present.push(Metric::Reps);
+    }
+    if set.duration_seconds.is_some() {
+        present.push(Metric::Duration);
+    }
+    if set.distance_meters.is_some() {
+        present.push(Metric::Distance);
+    }
+    present
+}
+
+fn routine_set_metrics(set: &RoutineSetInput) -> Vec<Metric> {
+    let mut present = Vec::new();
+    if set.weight_kg.is_some() {
+        present.push(Metric::Weight);
+    }
+    if set.reps.is_some() || set.rep_range.is_some() {
+        present.push(Metric::Reps);
+    }
+    if set.duration_seconds.is_some() {
+        present.push(Metric::Duration);
+    }
+    if set.distance_meters.is_some() {
+        present.push(Metric::Distance);
+    }
+    present
+}
+
+/// Looks up each template's type, from the client's cache where possible and
+/// with the remaining requests sent concurrently.
+async fn resolve_exercise_types<'a>(
+    client: &HevyClient,
+    template_ids: impl Iterator<Item = &'a str>,
+) -> Result<HashMap<String, Option<ExerciseType>>, ToolError> {
+    let mut types = HashMap::new();
+    let mut lookups = JoinSet::new();
+    for template_id in template_ids.collect::<BTreeSet<_>>() {
+        if template_id.trim().is_empty() {
+            continue;
+        }
+        if let Some(exercise_type) = client.exercise_types.get(template_id) {
+            types.insert(template_id.to_string(), Some(exercise_type.clone()));
+            continue;
+        }
+        let client = client.clone();
+        let template_id = template_id.to_string();
+        lookups.spawn(async move {
+            let result = client.get_exercise_template(&template_id).await;
+            (template_id, result)
+        });
+    }
+
+    while let Some(lookup) = lookups.join_next().await {
+        let (template_id, result) = lookup
+            .map_err(|e| ExecutionError(format!("Error fetching exercise templates: {}", e)))?;
+        let exercise_type = match result {
+            Ok(template) => Some(template.exercise_type),
+            Err(HevyError::NotFound(_)) => None,
+            Err(e) => {
+                return Err(ExecutionError(format!(
+                    "Error fetching exercise template {}: {}",
+                    template_id, e
+                )));
+            }
+        };
+        types.insert(template_id, exercise_type);
+    }
+    Ok(types)
+}
+
+/// Whether `set` was copied unchanged from the stored workout or routine the
+/// write is based on. Hevy already accepted those sets, so the per-set rules
+/// are only applied to the ones the caller supplied.
+fn is_copied<S: PartialEq>(copied: &[(&str, &[S])], template_id: &str, set: &S) -> bool {
+    copied
+        .iter()
+        .any(|(copied_template_id, sets)| *copied_template_id == template_id && sets.contains(set))
+}
+
+fn check_template(
+    errors: &mut ValidationErrors,
+    path: &str,
+    template_id: &str,
+    types: &HashMap<String, Option<ExerciseType>>,
+) -> Option<ExerciseType> {
+    if template_id.trim().is_empty() {
+        errors.push(
+            format!("{}.exercise_template_id", path),
+            "must not be empty",
+        );
+        return None;
+    }
+    match types.get(template_id) {
+        Some(Some(exercise_type)) => Some(exercise_type.clone()),
+        _ => {
+            errors.push(
+                format!("{}.exercise_template_id", path),
+                format!("exercise template {} does not exist", template_id),
+            );
+            None
+        }
+    }
+}
+
+/// Checks `workout` before it is written. `copied` holds the exercises of the
+/// stored workout or routine it was built from, if any. Every metric of the
+/// exercise type is only required when the caller wrote the whole workout,
+/// since edits to stored sets keep whatever the caller left out.
+pub(crate) async fn validate_workout(
+    client: &HevyClient,
+    workout: &WorkoutInput,
+    copied: &[WorkoutExerciseInput],
+) -> Result<(), ToolError> {
+    let types = resolve_exercise_types(
+        client,
+        workout
+            .exercises
+            .iter()
+            .map(|exercise| exercise.exercise_template_id.as_str()),
+    )
+    .await?;
+    check_workout(workout, copied, &types).into_result()
+}
+
+fn check_workout(
+    workout: &WorkoutInput,
+    copied: &[WorkoutExerciseInput],
+    types: &HashMap<String, Option<ExerciseType>>,
+) -> ValidationErrors {
+    let require_all = copied.is_empty();
+    let copied: Vec<(&str, &[SetInput])> = copied
+        .iter()
+        .map(|exercise| {
+            (
+                exercise.exercise_template_id.as_str(),
+                exercise.sets.as_slice(),
+            )
+        })
+        .collect();
+    let mut errors = ValidationErrors::default();
+
+    if workout.title.trim().is_empty() {
+        errors.push("title", "must not be empty");
+    }
+    if let Some(end_time) = workout.end_time
+        && end_time <= workout.start_time
+    {
+        errors.push("end_time", "must be after start_time");
+    }
+
+    for (exercise_index, exercise) in workout.exercises.iter().enumerate() {
+        let path = format!("exercises[{}]", exercise_index);
+        let exercise_type =
+            check_template(&mut errors, &path, &exercise.exercise_template_id, types);
+
+        for (set_index, set) in exercise.sets.iter().enumerate() {
+            if is_copied(&copied, &exercise.exercise_template_id, set) {
+                continue;
+            }
+            let set_path = format!("{}.sets[{}]", path, set_index);
+            if let Some(exercise_type) = &exercise_type {
+                check_metrics(
+                    &mut errors,
+                    &set_path,
+                    exercise_type,
+                    &workout_set_metrics(set),
+                    require_all,
+                );
+            }
+            check_non_negative(&mut errors, &set_path, "weight_kg", set.weight_kg);
+            check_non_negative(
+                &mut errors,
+                &set_path,
+                "distance_meters",
+                set.distance_meters,
+            );
+            if let Some(rpe) = set.rpe
+                && !RPE_VALUES.contains(&rpe)
+            {
+                errors.push(
+                    format!("{}.rpe", set_path),
+                    format!("must be 6, 7, 7.5, 8, 8.5, 9, 9.5 or 10, got {}", rpe),
+                );
+            }
+        }
+    }
+
+    errors
+}
+
+/// Checks `routine` before it is written. `copied` holds the exercises of the
+/// stored routine it was built from, if any.
+pub(crate) async fn validate_routine(
+    client: &HevyClient,
+    routine: &RoutineInput,
+    copied: &[RoutineExerciseInput],
+) -> Result<(), ToolError> {
+    let types = resolve_exercise_types(
+        client,
+        routine
+            .exercises
+            .iter()
+            .map(|exercise| exercise.exercise_template_id.as_str()),
+    )
+    .await?;
+    check_routine(routine, copied, &types).into_result()
+}
+
+fn check_routine(
+    routine: &RoutineInput,
+    copied: &[RoutineExerciseInput],
+    types: &HashMap<String, Option<ExerciseType>>,
+) -> ValidationErrors {
+    let copied: Vec<(&str, &[RoutineSetInput])> = copied
+        .iter()
+        .map(|exercise| {
+            (
+                exercise.exercise_template_id.as_str(),
+                exercise.sets.as_slice(),
+            )
+        })
+        .collect();
+    let mut errors = ValidationErrors::default();
+
+    if routine.title.trim().is_empty() {
+        errors.push("title", "must not be empty");
+    }
+
+    for (exercise_index, exercise) in routine.exercises.iter().enumerate() {
+        let path = format!("exercises[{}]", exercise_index);
+        let exercise_type =
+            check_template(&mut errors, &path, &exercise.exercise_template_id, types);
+
+        for (set_index, set) in exercise.sets.iter().enumerate() {
+            if is_copied(&copied, &exercise.exercise_template_id, set) {
+                continue;
+            }
+            let set_path = format!("{}.sets[{}]", path, set_index);
+            if let Some(exercise_type) = &exercise_type {
+                check_metrics(
+                    &mut errors,
+                    &set_path,
+                    exercise_type,
+                    &routine_set_metrics(set),
+                    false,
+                );
+            }
+            check_non_negative(&mut errors, &set_path, "weight_kg", set.weight_kg);
+            check_non_negative(
+                &mut errors,
+                &set_path,
+                "distance_meters",
+                set.distance_meters,
+            );
+            if let Some(range) = &set.rep_range
+                && range.start > range.end
+            {
+                errors.push(
+                    format!("{}.rep_range", set_path),
+                    format!(
+                        "start ({}) must not be greater than end ({})",
+                        range.start, range.end
+                    ),
+                );
+            }
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, from_value, json};
+
+    use super::*;
+
+    fn types() -> HashMap<String, Option<ExerciseType>> {
+        [
+            ("bench", ExerciseType::WeightReps),
+            ("push_up", ExerciseType::Reps),
+            ("plank", ExerciseType::Duration),
+            ("carry", ExerciseType::WeightDuration),
+            ("run", ExerciseType::DistanceDuration),
+            ("hold", ExerciseType::Weight),
+            ("custom", ExerciseType::Unknown),
+        ]
+        .into_iter()
+        .map(|(id, exercise_type)| (id.to_string(), Some(exercise_type)))
+        .collect()
+    }
+
+    fn workout(exercises: Value) -> WorkoutInput {
+        from_value(json!({
+            "title": "Push day",
+            "is_private": false,
+            "start_time": "2024-01-01T10:00:00Z",
+            "end_time": "2024-01-01T11:00:00Z",
+            "exercises": exercises,
+        }))
+        .unwrap()
+    }
+
+    fn single_set(template_id: &str, set: Value) -> WorkoutInput {
+        workout(json!([{ "exercise_template_id": template_id, "sets": [set] }]))
+    }
+
+    /// The paths of the problems found, in order.
+    fn problems(errors: ValidationErrors) -> Vec<String> {
+        errors.0.into_iter().map(|(path, _)| path).collect()
+    }
+
+    fn check_new(workout: &WorkoutInput) -> Vec<String> {
+        problems(check_workout(workout, &[], &types()))
+    }
+
+    #[test]
+    fn each_type_takes_exactly_its_metrics() {
+        let complete = [
+            ("bench", json!({ "weight_kg": 100.0, "reps": 5 })),
+            ("push_up", json!({ "reps": 20 })),
+            ("plank", json!({ "duration_seconds": 60 })),
+            (
+                "carry",
+                json!({ "weight_kg": 40.0, "duration_seconds": 30 }),
+            ),
+            (
+                "run",
+                json!({ "distance_meters": 5000.0, "duration_seconds": 1500 }),
+            ),
+            ("hold", json!({ "weight_kg": 20.0 })),
+        ];
+        for (template_id, set) in complete {
+            assert_eq!(
+                check_new(&single_set(template_id, set)),
+                Vec::<String>::new(),
+                "{}",
+                template_id
+            );
+        }
+    }
+
+    #[test]
+    fn new_workout_sets_need_every_metric_of_their_type() {
+        assert_eq!(
+            check_new(&single_set("bench", json!({ "reps": 5 }))),
+            ["exercises[0].sets[0].weight_kg"]
+        );
+        assert_eq!(
+            check_new(&single_set("run", json!({ "duration_seconds": 1500 }))),
+            ["exercises[0].sets[0].distance_meters"]
+        );
+    }
+
+    #[test]
+    fn metrics_of_other_types_are_rejected() {
+        assert_eq!(
+            check_new(&single_set(
+                "push_up",
+                json!({ "reps": 20, "weight_kg": 10.0 })
+            )),
+            ["exercises[0].sets[0].weight_kg"]
+        );
+        assert_eq!(
+            check_new(&single_set(
+                "plank",
+                json!({ "duration_seconds": 60, "distance_meters": 1.0 })
+            )),
+            ["exercises[0].sets[0].distance_meters"]
+        );
+    }
+
+    #[test]
+    fn unknown_types_are_not_checked() {
+        assert!(check_new(&single_set("custom", json!({ "custom_metric": 3.0 }))).is_empty());
+    }
+
+    #[test]
+    fn unknown_templates_are_reported() {
+        assert_eq!(
+            check_new(&single_set("missing", json!({ "reps": 5 }))),
+            ["exercises[0].exercise_template_id"]
+        );
+    }
+
+    #[test]
+    fn rpe_must_be_a_value_hevy_offers() {
+        for rpe in RPE_VALUES {
+            let set = json!({ "weight_kg": 100.0, "reps": 5, "rpe": rpe });
+            assert!(check_new(&single_set("bench", set)).is_empty(), "{}", rpe);
+        }
+        for rpe in [5.5, 6.5, 7.25, 10.5] {
+            let set = json!({ "weight_kg": 100.0, "reps": 5, "rpe": rpe });
+            assert_eq!(
+                check_new(&single_set("bench", set)),
+                ["exercises[0].sets[0].rpe"],
+                "{}",
+                rpe
+            );
+        }
+    }
+
+    #[test]
+    fn copied_sets_are_not_checked_and_new_ones_may_be_partial() {
+        // Hevy accepted this set before, even though it has no weight.
+        let copied = single_set("bench", json!({ "reps": 5 })).exercises;
+        let edited = workout(json!([{
+            "exercise_template_id": "bench",
+            "sets": [{ "reps": 5 }, { "weight_kg": 60.0 }],
+        }]));
+        assert!(problems(check_workout(&edited, &copied, &types())).is_empty());
+        assert_eq!(
+            check_new(&edited),
+            [
+                "exercises[0].sets[0].weight_kg",
+                "exercises[0].sets[1].reps"
+            ]
+        );
+
+        let wrong_metric = workout(json!([{
+            "exercise_template_id": "bench",
+            "sets": [{ "reps": 5 }, { "duration_seconds": 60 }],
+        }]));
+        assert_eq!(
+            problems(check_workout(&wrong_metric, &copied, &types())),
+            ["exercises[0].sets[1].duration_seconds"]
+        );
+    }
+
+    #[test]
+    fn sets_copied_from_another_template_are_checked() {
+        let copied = single_set("push_up", json!({ "reps": 5 })).exercises;
+        assert_eq!(
+            problems(check_workout(
+                &single_set("bench", json!({ "reps": 5, "duration_seconds": 60 })),
+                &copied,
+                &types()
+            )),
+            ["exercises[0].sets[0].duration_seconds"]
+        );
+    }
+
+    #[test]
+    fn routine_sets_may_leave_metrics_out() {
+        let routine: RoutineInput = from_value(json!({
+            "title": "Push day",
+            "exercises": [{
+                "exercise_template_id": "bench",
+                "sets": [
+                    { "rep_range": { "start": 8, "end": 12 } },
+                    { "rep_range": { "start": 12, "end": 8 } },
+                    { "distance_meters": 10.0 },
+                ],
+            }],
+        }))
+        .unwrap();
+        assert_eq!(
+            problems(check_routine(&routine, &[], &types())),
+            [
+                "exercises[0].sets[1].rep_range",
+                "exercises[0].sets[2].distance_meters"
+            ]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use mcp_spec::protocol::{ErrorData, JsonRpcError, JsonRpcMessage, PARSE_ERROR};
+use serde::Serialize;
+use serde_json::{from_str, to_string};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, stdin, stdout};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::HevyRouter;
+use crate::router::{
+    ConfirmationStore, ProtocolVersion, RequestRouter, ResourceSubscriptions, ToolPolicy,
+};
+
+/// Serves a single MCP session over newline-delimited JSON-RPC on stdin and
+/// stdout, returning when stdin closes.
+pub async fn serve(router: HevyRouter) -> Result<(), StdioError> {
+    let api_key = router
+        .default_api_key
+        .clone()
+        .ok_or(StdioError::MissingApiKey)?;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = stdout();
+        while let Some(line) = receiver.recv().await {
+            stdout.write_all(line.as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+        Ok::<_, std::io::Error>(())
+    });
+
+    let notifications = sender.clone();
+    let subscriptions = ResourceSubscriptions::new(Arc::new(move |notification| {
+        send(&notifications, &notification)
+    }));
+    let progress = sender.clone();
+    let client_lease = Arc::new(router.lease_client(&api_key));
+    let mut request_router = RequestRouter::new(
+        Arc::new(router),
+        client_lease,
+        Arc::new(ConfirmationStore::default()),
+        Arc::new(ToolPolicy::default()),
+        Arc::new(subscriptions),
+    )
+    .with_notifications(Arc::new(move |notification| send(&progress, &notification)));
+
+    info!("Serving MCP over stdio");
+    let mut lines = BufReader::new(stdin()).lines();
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|err| StdioError::Io(err.to_string()))?
+    {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match from_str::<JsonRpcMessage>(&line) {
+            Ok(JsonRpcMessage::Request(request)) => {
+                if request.method == "initialize" {
+                    request_router =
+                        request_router.with_protocol_version(ProtocolVersion::negotiate(&request));
+                }
+                // Requests run concurrently so a slow tool call does not hold up the rest.
+                let request_router = request_router.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    let response = request_router.respond(request).await;
+                    send(&sender, &response);
+                });
+            }
+            // Notifications and client replies need no answer.
+            Ok(_) => {}
+            Err(err) => {
+                warn!("Malformed message on stdin: {}", err);
+                send(
+                    &sender,
+                    &JsonRpcMessage::Error(JsonRpcError {
+                        jsonrpc: "2.0".to_string(),
+                        id: None,
+                        error: ErrorData {
+                            code: PARSE_ERROR,
+                            message: format!("Invalid JSON: {}", err),
+                            data: None,
+                        },
+                    }),
+                );
+            }
+        }
+    }
+
+    // Let in-flight requests finish writing before stdout closes.
+    drop(request_router);
+    drop(sender);
+    writer
+        .await
+        .map_err(|err| StdioError::Io(err.to_string()))?
+        .map_err(|err| StdioError::Io(err.to_string()))
+}
+
+fn send<T: Serialize>(sender: &mpsc::UnboundedSender<String>, message: &T) {
+    if let Ok(line) = to_string(message) {
+        // The writer only stops once stdout is gone, when nobody is listening anyway.
+        let _ = sender.send(line);
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum StdioError {
+    #[error("HEVY_API_KEY is required in stdio mode")]
+    MissingApiKey,
+    #[error("stdio error: {0}")]
+    Io(String),
+}
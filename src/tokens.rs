@@ -0,0 +1,407 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use aes_gcm::aead::OsRng;
+use aes_gcm::aead::rand_core::RngCore;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+pub use crate::crypto::generate_master_key;
+use crate::crypto::{FileVersion, MasterKey, Sealed, lock_file, to_hex, write_private_file};
+
+/// Server-issued tokens start with this, which tells them apart from Hevy API keys.
+pub const TOKEN_PREFIX: &str = "hevy_tok_";
+
+const TOKEN_BYTES: usize = 32;
+
+#[derive(Error, Debug)]
+pub enum TokenError {
+    #[error("{0}")]
+    Config(String),
+    #[error("token store error: {0}")]
+    Io(String),
+    #[error("failed to decrypt the Hevy key for token {0}; check HEVY_MASTER_KEY")]
+    Decrypt(String),
+    #[error("no token matches id {0}")]
+    NotFound(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TokenInfo {
+    /// SHA-256 of the token. The token itself is never stored.
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredToken {
+    #[serde(flatten)]
+    info: TokenInfo,
+    #[serde(flatten)]
+    api_key: Sealed,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct StoreFile {
+    tokens: Vec<StoredToken>,
+}
+
+#[derive(Default)]
+struct Loaded {
+    tokens: Vec<StoredToken>,
+    version: Option<FileVersion>,
+}
+
+/// Opaque access tokens mapped to Hevy API keys, persisted as a JSON file with
+/// every key encrypted under the master key.
+pub struct TokenStore {
+    path: PathBuf,
+    master_key: MasterKey,
+    loaded: RwLock<Loaded>,
+}
+
+impl TokenStore {
+    /// Opens the store named by `HEVY_TOKEN_STORE` with `HEVY_MASTER_KEY`, or
+    /// returns `None` when no store is configured.
+    pub fn from_env() -> Result<Option<Self>, TokenError> {
+        let path = match env::var("HEVY_TOKEN_STORE") {
+            Ok(path) if !path.trim().is_empty() => path,
+            Ok(_) | Err(env::VarError::NotPresent) => return Ok(None),
+            Err(env::VarError::NotUnicode(_)) => {
+                return Err(TokenError::Config(
+                    "HEVY_TOKEN_STORE must be valid UTF-8".to_string(),
+                ));
+            }
+        };
+        let master_key = env::var("HEVY_MASTER_KEY").map_err(|_| {
+            TokenError::Config(
+                "HEVY_MASTER_KEY is required when HEVY_TOKEN_STORE is set".to_string(),
+            )
+        })?;
+        Self::open(path, &master_key).map(Some)
+    }
+
+    pub fn open(path: impl Into<PathBuf>, master_key: &str) -> Result<Self, TokenError> {
+        let store = Self {
+            path: path.into(),
+            master_key: MasterKey::parse(master_key).map_err(TokenError::Config)?,
+            loaded: RwLock::new(Loaded::default()),
+        };
+        store.refresh(&mut store.loaded.write().unwrap())?;
+        Ok(store)
+    }
+
+    /// Stores `api_key` and returns the new token, which is only ever shown here.
+    pub fn issue(
+        &self,
+        api_key: &str,
+        label: Option<String>,
+    ) -> Result<(String, TokenInfo), TokenError> {
+        let token = random_token(TOKEN_PREFIX);
+        let info = TokenInfo {
+            id: fingerprint(&token),
+            label,
+            created_at: Utc::now(),
+        };
+
+        let api_key = self
+            .master_key
+            .seal(api_key, &info.id)
+            .map_err(TokenError::Io)?;
+
+        let mut loaded = self.loaded.write().unwrap();
+        // Another process may have written the file since the last refresh,
+        // so reload it under the lock before changing it.
+        let _lock = lock_file(&self.path).map_err(|err| self.io_error(err))?;
+        loaded.version = None;
+        self.refresh(&mut loaded)?;
+        loaded.tokens.push(StoredToken {
+            info: info.clone(),
+            api_key,
+        });
+        self.save(&mut loaded)?;
+        Ok((token, info))
+    }
+
+    /// The Hevy API key for `token`, or `None` if the token is unknown or revoked.
+    pub fn resolve(&self, token: &str) -> Result<Option<String>, TokenError> {
+        self.resolve_id(&fingerprint(token))
+    }
+
+    /// The Hevy API key for the token with id `id`, or `None` if it was revoked.
+    pub(crate) fn resolve_id(&self, id: &str) -> Result<Option<String>, TokenError> {
+        let mut loaded = self.loaded.write().unwrap();
+        // Picks up tokens issued or revoked by the CLI while the server runs.
+        self.refresh(&mut loaded)?;
+        let Some(stored) = loaded.tokens.iter().find(|stored| stored.info.id == id) else {
+            return Ok(None);
+        };
+
+        self.master_key
+            .open(&stored.api_key, id)
+            .map(Some)
+            .ok_or_else(|| TokenError::Decrypt(id.to_string()))
+    }
+
+    /// Whether the token with id `id` is still valid.
+    pub(crate) fn contains(&self, id: &str) -> Result<bool, TokenError> {
+        let mut loaded = self.loaded.write().unwrap();
+        self.refresh(&mut loaded)?;
+        Ok(loaded.tokens.iter().any(|stored| stored.info.id == id))
+    }
+
+    pub fn list(&self) -> Result<Vec<TokenInfo>, TokenError> {
+        let mut loaded = self.loaded.write().unwrap();
+        self.refresh(&mut loaded)?;
+        Ok(loaded
+            .tokens
+            .iter()
+            .map(|stored| stored.info.clone())
+            .collect())
+    }
+
+    /// Revokes the token whose id is, or uniquely starts with, `id`.
+    pub fn revoke(&self, id: &str) -> Result<TokenInfo, TokenError> {
+        let mut loaded = self.loaded.write().unwrap();
+        let _lock = lock_file(&self.path).map_err(|err| self.io_error(err))?;
+        loaded.version = None;
+        self.refresh(&mut loaded)?;
+        let matches: Vec<usize> = loaded
+            .tokens
+            .iter()
+            .enumerate()
+            .filter(|(_, stored)| !id.is_empty() && stored.info.id.starts_with(id))
+            .map(|(index, _)| index)
+            .collect();
+        let [index] = matches[..] else {
+            return Err(TokenError::NotFound(id.to_string()));
+        };
+        let removed = loaded.tokens.remove(index);
+        self.save(&mut loaded)?;
+        Ok(removed.info)
+    }
+
+    fn refresh(&self, loaded: &mut Loaded) -> Result<(), TokenError> {
+        let version = match fs::metadata(&self.path) {
+            Ok(metadata) => FileVersion::of(&metadata),
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                *loaded = Loaded::default();
+                return Ok(());
+            }
+            Err(err) => return Err(self.io_error(err)),
+        };
+        if loaded.version == Some(version) {
+            return Ok(());
+        }
+
+        let contents = fs::read(&self.path).map_err(|err| self.io_error(err))?;
+        let file: StoreFile = serde_json::from_slice(&contents).map_err(|err| {
+            TokenError::Io(format!(
+                "{} is not a valid token store: {}",
+                self.path.display(),
+                err
+            ))
+        })?;
+        *loaded = Loaded {
+            tokens: file.tokens,
+            version: Some(version),
+        };
+        Ok(())
+    }
+
+    fn save(&self, loaded: &mut Loaded) -> Result<(), TokenError> {
+        let file = StoreFile {
+            tokens: loaded.tokens.clone(),
+        };
+        let contents =
+            serde_json::to_vec_pretty(&file).map_err(|err| TokenError::Io(err.to_string()))?;
+
+        write_private_file(&self.path, &contents).map_err(|err| self.io_error(err))?;
+
+        loaded.version = fs::metadata(&self.path)
+            .map(|metadata| FileVersion::of(&metadata))
+            .ok();
+        Ok(())
+    }
+
+    fn io_error(&self, err: std::io::Error) -> TokenError {
+        TokenError::Io(format!("{}: {}", self.path.display(), err))
+    }
+}
+
+pub fn is_token(credential: &str) -> bool {
+    credential.starts_with(TOKEN_PREFIX)
+}
+
+/// `prefix` followed by 32 random bytes as hex.
+pub(crate) fn random_token(prefix: &str) -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    format!("{}{}", prefix, to_hex(&bytes))
+}
+
+/// SHA-256 of a secret as hex, for identifying it without keeping it around.
+pub(crate) fn fingerprint(secret: &str) -> String {
+    to_hex(&Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::*;
+
+    /// A store in a file of its own, removed when the test ends.
+    struct TempStore {
+        path: PathBuf,
+        store: TokenStore,
+    }
+
+    impl TempStore {
+        fn new() -> Self {
+            let path = env::temp_dir().join(format!("hevy-tokens-{}.json", random_token("")));
+            let store = TokenStore::open(&path, &generate_master_key()).unwrap();
+            Self { path, store }
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+            let _ = fs::remove_file(self.path.with_extension("lock"));
+        }
+    }
+
+    #[test]
+    fn issued_token_resolves_to_its_key() {
+        let temp = TempStore::new();
+        let (token, info) = temp
+            .store
+            .issue("hevy-api-key", Some("alice".to_string()))
+            .unwrap();
+        assert!(is_token(&token));
+        assert_eq!(info.id, fingerprint(&token));
+        assert_eq!(
+            temp.store.resolve(&token).unwrap().as_deref(),
+            Some("hevy-api-key")
+        );
+        assert!(temp.store.contains(&info.id).unwrap());
+    }
+
+    #[test]
+    fn store_keeps_neither_the_token_nor_the_key() {
+        let temp = TempStore::new();
+        let (token, _) = temp.store.issue("hevy-api-key", None).unwrap();
+        let contents = fs::read_to_string(&temp.path).unwrap();
+        assert!(!contents.contains(&token));
+        assert!(!contents.contains("hevy-api-key"));
+    }
+
+    #[test]
+    fn unknown_token_does_not_resolve() {
+        let temp = TempStore::new();
+        temp.store.issue("hevy-api-key", None).unwrap();
+        assert_eq!(
+            temp.store.resolve(&random_token(TOKEN_PREFIX)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn revoked_token_no_longer_resolves() {
+        let temp = TempStore::new();
+        let (token, info) = temp.store.issue("hevy-api-key", None).unwrap();
+        let revoked = temp.store.revoke(&info.id[..8]).unwrap();
+        assert_eq!(revoked.id, info.id);
+        assert_eq!(temp.store.resolve(&token).unwrap(), None);
+        assert!(!temp.store.contains(&info.id).unwrap());
+        assert!(temp.store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn revoke_needs_a_unique_id_prefix() {
+        let temp = TempStore::new();
+        temp.store.issue("first-key", None).unwrap();
+        temp.store.issue("second-key", None).unwrap();
+        assert!(matches!(
+            temp.store.revoke(""),
+            Err(TokenError::NotFound(_))
+        ));
+        assert!(matches!(
+            temp.store.revoke("not-an-id"),
+            Err(TokenError::NotFound(_))
+        ));
+        assert_eq!(temp.store.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn another_store_on_the_same_file_sees_new_tokens() {
+        let temp = TempStore::new();
+        let master_key = generate_master_key();
+        let store = TokenStore::open(&temp.path, &master_key).unwrap();
+        let (token, _) = store.issue("hevy-api-key", None).unwrap();
+        let other = TokenStore::open(&temp.path, &master_key).unwrap();
+        assert_eq!(
+            other.resolve(&token).unwrap().as_deref(),
+            Some("hevy-api-key")
+        );
+    }
+
+    #[test]
+    fn stores_sharing_a_file_keep_each_others_changes() {
+        let temp = TempStore::new();
+        let master_key = generate_master_key();
+        let stores: Vec<TokenStore> = (0..4)
+            .map(|_| TokenStore::open(&temp.path, &master_key).unwrap())
+            .collect();
+        let revoked = stores[0].issue("revoked-key", None).unwrap().1;
+
+        let barrier = Barrier::new(stores.len());
+        let issued: Vec<TokenInfo> = thread::scope(|scope| {
+            let handles: Vec<_> = stores
+                .iter()
+                .enumerate()
+                .map(|(index, store)| {
+                    let barrier = &barrier;
+                    let revoked = &revoked;
+                    scope.spawn(move || {
+                        barrier.wait();
+                        if index == 0 {
+                            store.revoke(&revoked.id).unwrap();
+                        }
+                        (0..8)
+                            .map(|_| store.issue("hevy-api-key", None).unwrap().1)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        for store in &stores {
+            let listed = store.list().unwrap();
+            assert_eq!(listed.len(), issued.len());
+            assert!(issued.iter().all(|info| store.contains(&info.id).unwrap()));
+            assert!(!store.contains(&revoked.id).unwrap());
+        }
+    }
+
+    #[test]
+    fn key_from_another_master_key_fails_to_decrypt() {
+        let temp = TempStore::new();
+        let (token, _) = temp.store.issue("hevy-api-key", None).unwrap();
+        let other = TokenStore::open(&temp.path, &generate_master_key()).unwrap();
+        assert!(matches!(other.resolve(&token), Err(TokenError::Decrypt(_))));
+    }
+}